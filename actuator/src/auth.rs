@@ -0,0 +1,101 @@
+use std::fmt::{Debug, Formatter};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Credentials of HTTP basic authentication an endpoint can require.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BasicAuth {
+    /// Base64 encoding of `username:password`, as sent in the header.
+    token: String,
+}
+
+impl BasicAuth {
+    /// Creates the credentials, the username must not contain a colon.
+    pub fn new(username: impl AsRef<str>, password: impl AsRef<str>) -> Self {
+        let credentials = format!("{}:{}", username.as_ref(), password.as_ref());
+        Self {
+            token: base64(credentials.as_bytes()),
+        }
+    }
+
+    /// Reports whether the value of an `Authorization` header carries the
+    /// credentials, a missing header never does.
+    pub(crate) fn verify(&self, header: Option<&str>) -> bool {
+        let Some((scheme, token)) = header.and_then(|header| header.trim().split_once(' ')) else {
+            return false;
+        };
+        scheme.eq_ignore_ascii_case("basic") && constant_time_eq(token.trim(), &self.token)
+    }
+}
+
+impl Debug for BasicAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BasicAuth(**********)")
+    }
+}
+
+/// Compares without exiting at the first difference, so the time taken does
+/// not reveal how much of the credentials matched.
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Encodes with the standard alphabet and padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | u32::from(*byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(b"admin:s3cret"), "YWRtaW46czNjcmV0");
+    }
+
+    #[test]
+    fn test_verify() {
+        let auth = BasicAuth::new("admin", "s3cret");
+        assert!(auth.verify(Some("Basic YWRtaW46czNjcmV0")));
+        assert!(auth.verify(Some("basic  YWRtaW46czNjcmV0 ")));
+        assert!(!auth.verify(None));
+        assert!(!auth.verify(Some("")));
+        assert!(!auth.verify(Some("Basic")));
+        assert!(!auth.verify(Some("Bearer YWRtaW46czNjcmV0")));
+        assert!(!auth.verify(Some(&format!("Basic {}", base64(b"admin:wrong")))));
+        assert!(!auth.verify(Some(&format!("Basic {}", base64(b"root:s3cret")))));
+    }
+
+    #[test]
+    fn test_debug_hides_credentials() {
+        assert_eq!(
+            format!("{:?}", BasicAuth::new("admin", "s3cret")),
+            "BasicAuth(**********)"
+        );
+    }
+}
@@ -0,0 +1,935 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::BasicAuth;
+use crate::envs::{self, DEFAULT_MASK_PATTERNS};
+use crate::error::ActuatorError;
+use crate::health::{HealthConfig, HealthStatus};
+use crate::metrics::SystemSource;
+
+/// Endpoints the actuator can expose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    any(
+        feature = "config-json",
+        feature = "config-toml",
+        feature = "config-yaml"
+    ),
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Endpoint {
+    Ping,
+    Info,
+    Health,
+    Env,
+    Metrics,
+    Shutdown,
+    ThreadDump,
+}
+
+impl Endpoint {
+    /// Every built-in endpoint.
+    pub(crate) const ALL: [Endpoint; 7] = [
+        Endpoint::Ping,
+        Endpoint::Info,
+        Endpoint::Health,
+        Endpoint::Env,
+        Endpoint::Metrics,
+        Endpoint::Shutdown,
+        Endpoint::ThreadDump,
+    ];
+
+    /// Path the endpoint is served on, relative to the configured base path.
+    pub fn path(&self) -> &'static str {
+        match self {
+            Endpoint::Ping => "/ping",
+            Endpoint::Info => "/info",
+            Endpoint::Health => "/health",
+            Endpoint::Env => "/env",
+            Endpoint::Metrics => "/metrics",
+            Endpoint::Shutdown => "/shutdown",
+            Endpoint::ThreadDump => "/threaddump",
+        }
+    }
+}
+
+/// Action terminating the process with an exit code.
+pub(crate) type ExitHandler = Arc<dyn Fn(i32) + Send + Sync>;
+
+/// How the actuator ends the process once the shutdown hooks ran.
+///
+/// The shutdown endpoint of the actix integration still sends on the channel of
+/// [`ShutdownStrategy::Callback`], but gracefully stops the actix server rather
+/// than exiting or signalling the process.
+#[derive(Debug, Clone)]
+pub enum ShutdownStrategy {
+    /// Runs the exit handler, [`std::process::exit`] by default, with the code
+    /// the shutdown was requested with, this one for a plain
+    /// [`Actuator::shutdown`](crate::Actuator::shutdown).
+    Exit(i32),
+    /// Sends `SIGTERM` to the process, so that it goes through the graceful
+    /// shutdown of its server. Runs the exit handler where signals are not
+    /// available.
+    Signal,
+    /// Sends the exit code on the channel without exiting, for the server loop
+    /// to stop on.
+    Callback(tokio::sync::mpsc::UnboundedSender<i32>),
+}
+
+/// Configuration the actuator is built from, created through [`Config::builder`].
+///
+/// `E` is the error type returned by the registered health checks.
+pub struct Config<E = ActuatorError> {
+    pub(crate) name: String,
+    pub(crate) env: String,
+    pub(crate) version: String,
+    pub(crate) package_version: String,
+    pub(crate) port: u16,
+    pub(crate) endpoints: Vec<Endpoint>,
+    pub(crate) base_path: String,
+    pub(crate) basic_auth: HashMap<Endpoint, BasicAuth>,
+    pub(crate) ping_response: String,
+    pub(crate) env_prefix: Option<String>,
+    pub(crate) env_mask: Vec<String>,
+    pub(crate) dotenv_path: Option<PathBuf>,
+    pub(crate) dotenv_override: bool,
+    /// Variables of the `.env` file, read when building.
+    pub(crate) dotenv: Vec<(String, String)>,
+    pub(crate) disk_mount_point: Option<String>,
+    pub(crate) metrics_history_size: usize,
+    pub(crate) cpu_usage_window: Option<usize>,
+    pub(crate) system_source: Option<Box<dyn SystemSource>>,
+    pub(crate) health_status_codes: HashMap<HealthStatus, u16>,
+    pub(crate) shutdown_timeout: Duration,
+    pub(crate) shutdown_token: Option<String>,
+    pub(crate) shutdown_strategy: ShutdownStrategy,
+    pub(crate) exit_handler: ExitHandler,
+    pub(crate) health: HealthConfig<E>,
+}
+
+impl Config {
+    /// Starts building a configuration, checks fail with [`ActuatorError`] unless
+    /// [`ConfigBuilder::health`] registers checks with another error type.
+    ///
+    /// ```
+    /// use rust_actuator::{Actuator, Config, Endpoint};
+    ///
+    /// let cfg = Config::builder()
+    ///     .name("orders")
+    ///     .env("production")
+    ///     .version("1.0.0")
+    ///     .port(8080)
+    ///     .endpoints([Endpoint::Ping, Endpoint::Health])
+    ///     .build()
+    ///     .unwrap();
+    /// let actuator = Actuator::new(cfg);
+    /// assert!(actuator.ping());
+    /// ```
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// Loads the configuration from a JSON file such as:
+    ///
+    /// ```json
+    /// {
+    ///     "name": "orders",
+    ///     "env": "production",
+    ///     "version": "1.0.0",
+    ///     "port": 8080,
+    ///     "endpoints": ["ping", "health", "threaddump"],
+    ///     "health": { "cache_duration": "30s", "timeout": "5s" }
+    /// }
+    /// ```
+    ///
+    /// Only `name` and `port` are required, durations are parsed by
+    /// [`parse_duration`](crate::parse_duration). The settings are validated
+    /// like [`ConfigBuilder::build`] does, health checks still have to be
+    /// registered in code.
+    #[cfg(feature = "config-json")]
+    pub fn from_json_file(path: impl AsRef<std::path::Path>) -> Result<Self, ActuatorError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    #[cfg(feature = "config-json")]
+    fn from_json(json: &str) -> Result<Self, ActuatorError> {
+        let file = serde_json::from_str(json)
+            .map_err(|err| ActuatorError::new(format!("invalid config: {err}")))?;
+        Self::from_file(file)
+    }
+
+    /// Loads the configuration from TOML such as:
+    ///
+    /// ```toml
+    /// name = "orders"
+    /// port = 8080
+    /// endpoints = ["ping", "health"]
+    ///
+    /// [health]
+    /// cache_duration = "30s"
+    /// ```
+    ///
+    /// Takes the same settings as the JSON files of `Config::from_json_file`,
+    /// validated alike.
+    #[cfg(feature = "config-toml")]
+    pub fn from_toml_str(toml: &str) -> Result<Self, ActuatorError> {
+        let file = toml::from_str(toml)
+            .map_err(|err| ActuatorError::new(format!("invalid config: {err}")))?;
+        Self::from_file(file)
+    }
+
+    /// Loads the configuration from YAML such as:
+    ///
+    /// ```yaml
+    /// name: orders
+    /// port: 8080
+    /// endpoints: [ping, health]
+    /// health:
+    ///   cache_duration: 30s
+    /// ```
+    ///
+    /// Takes the same settings as the JSON files of `Config::from_json_file`,
+    /// validated alike.
+    #[cfg(feature = "config-yaml")]
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, ActuatorError> {
+        let file = serde_yaml::from_str(yaml)
+            .map_err(|err| ActuatorError::new(format!("invalid config: {err}")))?;
+        Self::from_file(file)
+    }
+
+    #[cfg(any(
+        feature = "config-json",
+        feature = "config-toml",
+        feature = "config-yaml"
+    ))]
+    fn from_file(file: FileConfig) -> Result<Self, ActuatorError> {
+        let mut health = HealthConfig::default();
+        if let Some(cache_duration) = file.health.cache_duration {
+            health =
+                health.cache_duration(parse_duration("health.cache_duration", &cache_duration)?);
+        }
+        if let Some(timeout) = file.health.timeout {
+            health = health.timeout(parse_duration("health.timeout", &timeout)?);
+        }
+        Config::builder()
+            .name(file.name)
+            .env(file.env)
+            .version(file.version)
+            .port(file.port)
+            .endpoints(file.endpoints)
+            .health(health)
+            .build()
+    }
+}
+
+/// Settings the configuration files provide, whatever their format.
+#[cfg(any(
+    feature = "config-json",
+    feature = "config-toml",
+    feature = "config-yaml"
+))]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    name: String,
+    #[serde(default)]
+    env: String,
+    #[serde(default)]
+    version: String,
+    port: u16,
+    #[serde(default)]
+    endpoints: Vec<Endpoint>,
+    #[serde(default)]
+    health: FileHealthConfig,
+}
+
+#[cfg(any(
+    feature = "config-json",
+    feature = "config-toml",
+    feature = "config-yaml"
+))]
+#[derive(Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileHealthConfig {
+    cache_duration: Option<String>,
+    timeout: Option<String>,
+}
+
+#[cfg(any(
+    feature = "config-json",
+    feature = "config-toml",
+    feature = "config-yaml"
+))]
+fn parse_duration(field: &str, value: &str) -> Result<Duration, ActuatorError> {
+    crate::parse_duration(value).map_err(|err| ActuatorError::new(format!("{field}: {err}")))
+}
+
+impl<E> Config<E> {
+    /// Name of the application.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Environment the application runs in.
+    pub fn env(&self) -> &str {
+        &self.env
+    }
+
+    /// Version of the application.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Port the actuator endpoints are served on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Endpoints enabled for the application.
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    /// Path the endpoints are served under, empty when served at the root.
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    /// Path of the `.env` file whose variables the env endpoint serves, if any.
+    pub fn dotenv_path(&self) -> Option<&Path> {
+        self.dotenv_path.as_deref()
+    }
+}
+
+/// Fluent builder for [`Config`].
+pub struct ConfigBuilder<E = ActuatorError> {
+    name: String,
+    env: String,
+    version: String,
+    package_version: String,
+    port: u16,
+    endpoints: Vec<Endpoint>,
+    base_path: String,
+    basic_auth: HashMap<Endpoint, BasicAuth>,
+    ping_response: String,
+    env_prefix: Option<String>,
+    env_mask: Vec<String>,
+    dotenv_path: Option<PathBuf>,
+    dotenv_override: bool,
+    disk_mount_point: Option<String>,
+    metrics_history_size: usize,
+    cpu_usage_window: Option<usize>,
+    system_source: Option<Box<dyn SystemSource>>,
+    health_status_codes: HashMap<HealthStatus, u16>,
+    shutdown_timeout: Duration,
+    shutdown_token: Option<String>,
+    shutdown_strategy: ShutdownStrategy,
+    exit_handler: ExitHandler,
+    health: HealthConfig<E>,
+}
+
+impl<E> Default for ConfigBuilder<E> {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            env: String::new(),
+            version: String::new(),
+            package_version: String::new(),
+            port: 0,
+            endpoints: Vec::new(),
+            base_path: "/actuator".to_string(),
+            basic_auth: HashMap::new(),
+            ping_response: "pong".to_string(),
+            env_prefix: None,
+            env_mask: DEFAULT_MASK_PATTERNS.map(String::from).to_vec(),
+            dotenv_path: None,
+            dotenv_override: false,
+            disk_mount_point: None,
+            metrics_history_size: 60,
+            cpu_usage_window: None,
+            system_source: None,
+            health_status_codes: HashMap::new(),
+            shutdown_timeout: Duration::from_secs(10),
+            shutdown_token: None,
+            shutdown_strategy: ShutdownStrategy::Exit(0),
+            exit_handler: Arc::new(|code| process::exit(code)),
+            health: HealthConfig::default(),
+        }
+    }
+}
+
+impl<E> ConfigBuilder<E> {
+    /// Sets the name of the application, required.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the environment the application runs in.
+    pub fn env(mut self, env: impl Into<String>) -> Self {
+        self.env = env.into();
+        self
+    }
+
+    /// Sets the version of the application.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Sets the Cargo package version of the application reported in the runtime
+    /// info, typically `env!("CARGO_PKG_VERSION")` expanded in the application.
+    pub fn package_version(mut self, version: impl Into<String>) -> Self {
+        self.package_version = version.into();
+        self
+    }
+
+    /// Sets the port the actuator endpoints are served on, required.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Sets the endpoints to enable, each at most once.
+    pub fn endpoints(mut self, endpoints: impl IntoIterator<Item = Endpoint>) -> Self {
+        self.endpoints = endpoints.into_iter().collect();
+        self
+    }
+
+    /// Sets the path the framework integrations serve the endpoints under,
+    /// defaults to `/actuator`. It must start with a slash and not end with one,
+    /// an empty path serves the endpoints at the root.
+    pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Requires the credentials to call the endpoint through the framework
+    /// integrations, which answer with 401 otherwise. Endpoints without
+    /// credentials are open.
+    pub fn basic_auth(mut self, endpoint: Endpoint, credentials: BasicAuth) -> Self {
+        self.basic_auth.insert(endpoint, credentials);
+        self
+    }
+
+    /// Sets the payload the ping endpoint answers with, defaults to `pong`.
+    pub fn ping_response(mut self, payload: impl Into<String>) -> Self {
+        self.ping_response = payload.into();
+        self
+    }
+
+    /// Limits the env endpoint to the variables whose name starts with the prefix,
+    /// all of them are served otherwise.
+    pub fn env_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the patterns of the environment variables whose values the env
+    /// endpoint masks, matched as case-insensitive substrings of the names.
+    ///
+    /// Defaults to `password`, `secret`, `token`, `key` and `credential`.
+    pub fn env_mask<S: Into<String>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.env_mask = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Serves the variables of the `.env` file at the path on the env endpoint
+    /// too, read when building. The variables of the process win over the ones
+    /// of the file unless [`dotenv_override`](Self::dotenv_override) is set, the
+    /// file is never applied to the environment of the process.
+    pub fn dotenv_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dotenv_path = Some(path.into());
+        self
+    }
+
+    /// Makes the variables of the `.env` file win over the ones of the process,
+    /// off by default.
+    pub fn dotenv_override(mut self, dotenv_override: bool) -> Self {
+        self.dotenv_override = dotenv_override;
+        self
+    }
+
+    /// Limits the disk metrics to the disk mounted at the path, all the disks are
+    /// reported otherwise.
+    pub fn disk_mount_point(mut self, mount_point: impl Into<String>) -> Self {
+        self.disk_mount_point = Some(mount_point.into());
+        self
+    }
+
+    /// Sets how many samples the metrics sampler keeps, defaults to 60.
+    pub fn metrics_history_size(mut self, size: usize) -> Self {
+        self.metrics_history_size = size;
+        self
+    }
+
+    /// Makes [`Actuator::metrics`](crate::Actuator::metrics) report the global
+    /// CPU usage averaged over its last `window` calls instead of measured
+    /// since the previous refresh of sysinfo, for stable readings.
+    ///
+    /// Each reading covers the time since the previous call, so the calls have
+    /// to be at least [`sysinfo::MINIMUM_CPU_UPDATE_INTERVAL`] apart, e.g. by
+    /// polling the metrics endpoint periodically.
+    pub fn cpu_usage_window(mut self, window: usize) -> Self {
+        self.cpu_usage_window = Some(window);
+        self
+    }
+
+    /// Replaces the source of the memory, swap and CPU numbers of the host,
+    /// sysinfo by default.
+    pub fn system_source(mut self, source: impl SystemSource + 'static) -> Self {
+        self.system_source = Some(Box::new(source));
+        self
+    }
+
+    /// Sets the HTTP status code the framework integrations answer the health
+    /// endpoint with for the status, 200 for up and degraded and 503 for down by
+    /// default. The code must be within 100 and 599.
+    pub fn health_status_code(mut self, status: HealthStatus, code: u16) -> Self {
+        self.health_status_codes.insert(status, code);
+        self
+    }
+
+    /// Sets how long the shutdown hooks may run before the process exits anyway,
+    /// defaults to 10 seconds.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Requires the token to call the shutdown endpoint through the framework
+    /// integrations, in the `X-Shutdown-Token` header or the `token` query
+    /// parameter, which answer with 403 otherwise. The query parameter is
+    /// percent-decoded before being compared.
+    pub fn shutdown_token(mut self, token: impl Into<String>) -> Self {
+        self.shutdown_token = Some(token.into());
+        self
+    }
+
+    /// Sets how the process is ended on shutdown, by exiting with code 0 by
+    /// default.
+    pub fn shutdown_strategy(mut self, strategy: ShutdownStrategy) -> Self {
+        self.shutdown_strategy = strategy;
+        self
+    }
+
+    /// Replaces the action terminating the process on shutdown under
+    /// [`ShutdownStrategy::Exit`], which defaults to [`std::process::exit`].
+    pub fn with_exit_handler(mut self, handler: impl Fn(i32) + Send + Sync + 'static) -> Self {
+        self.exit_handler = Arc::new(handler);
+        self
+    }
+
+    /// Sets the health checks to evaluate, their error type becomes the one of
+    /// the configuration.
+    pub fn health<F>(self, health: HealthConfig<F>) -> ConfigBuilder<F> {
+        ConfigBuilder {
+            name: self.name,
+            env: self.env,
+            version: self.version,
+            package_version: self.package_version,
+            port: self.port,
+            endpoints: self.endpoints,
+            base_path: self.base_path,
+            basic_auth: self.basic_auth,
+            ping_response: self.ping_response,
+            env_prefix: self.env_prefix,
+            env_mask: self.env_mask,
+            dotenv_path: self.dotenv_path,
+            dotenv_override: self.dotenv_override,
+            disk_mount_point: self.disk_mount_point,
+            metrics_history_size: self.metrics_history_size,
+            cpu_usage_window: self.cpu_usage_window,
+            system_source: self.system_source,
+            health_status_codes: self.health_status_codes,
+            shutdown_timeout: self.shutdown_timeout,
+            shutdown_token: self.shutdown_token,
+            shutdown_strategy: self.shutdown_strategy,
+            exit_handler: self.exit_handler,
+            health,
+        }
+    }
+
+    /// Validates the settings and builds the configuration.
+    pub fn build(self) -> Result<Config<E>, ActuatorError> {
+        if self.name.is_empty() {
+            return Err(ActuatorError::new("name must not be empty"));
+        }
+        if self.port == 0 {
+            return Err(ActuatorError::new("port must not be zero"));
+        }
+        let mut seen = HashSet::with_capacity(self.endpoints.len());
+        if let Some(endpoint) = self
+            .endpoints
+            .iter()
+            .find(|endpoint| !seen.insert(**endpoint))
+        {
+            return Err(ActuatorError::new(format!(
+                "endpoint {endpoint:?} is listed more than once"
+            )));
+        }
+        if self.metrics_history_size == 0 {
+            return Err(ActuatorError::new("metrics history size must not be zero"));
+        }
+        if self.cpu_usage_window == Some(0) {
+            return Err(ActuatorError::new("cpu usage window must not be zero"));
+        }
+        if let Some((status, code)) = self
+            .health_status_codes
+            .iter()
+            .find(|(_, code)| !(100..=599).contains(*code))
+        {
+            return Err(ActuatorError::new(format!(
+                "health status code {code} of {status:?} is not a valid HTTP status"
+            )));
+        }
+        if !self.base_path.is_empty()
+            && (!self.base_path.starts_with('/') || self.base_path.ends_with('/'))
+        {
+            return Err(ActuatorError::new(format!(
+                "base path {:?} must start with a slash and not end with one",
+                self.base_path
+            )));
+        }
+        let dotenv = match &self.dotenv_path {
+            Some(path) => envs::parse_dotenv(&std::fs::read_to_string(path).map_err(|err| {
+                ActuatorError::new(format!(
+                    "cannot read the .env file {}: {err}",
+                    path.display()
+                ))
+            })?),
+            None => Vec::new(),
+        };
+        Ok(Config {
+            name: self.name,
+            env: self.env,
+            version: self.version,
+            package_version: self.package_version,
+            port: self.port,
+            endpoints: self.endpoints,
+            base_path: self.base_path,
+            basic_auth: self.basic_auth,
+            ping_response: self.ping_response,
+            env_prefix: self.env_prefix,
+            env_mask: self.env_mask,
+            dotenv_path: self.dotenv_path,
+            dotenv_override: self.dotenv_override,
+            dotenv,
+            disk_mount_point: self.disk_mount_point,
+            metrics_history_size: self.metrics_history_size,
+            cpu_usage_window: self.cpu_usage_window,
+            system_source: self.system_source,
+            health_status_codes: self.health_status_codes,
+            shutdown_timeout: self.shutdown_timeout,
+            shutdown_token: self.shutdown_token,
+            shutdown_strategy: self.shutdown_strategy,
+            exit_handler: self.exit_handler,
+            health: self.health,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> ConfigBuilder {
+        Config::builder()
+            .name("orders")
+            .env("test")
+            .version("1.0.0")
+            .port(8080)
+            .endpoints([Endpoint::Ping, Endpoint::Health])
+    }
+
+    #[test]
+    fn test_build() {
+        let cfg = builder().build().unwrap();
+        assert_eq!(cfg.name(), "orders");
+        assert_eq!(cfg.env(), "test");
+        assert_eq!(cfg.version(), "1.0.0");
+        assert_eq!(cfg.port(), 8080);
+        assert_eq!(cfg.endpoints(), [Endpoint::Ping, Endpoint::Health]);
+    }
+
+    #[test]
+    fn test_build_dotenv_path() {
+        let path = std::env::temp_dir().join(format!("actuator-{}.env", process::id()));
+        std::fs::write(&path, "ORDERS_QUEUE=orders\n").unwrap();
+        let cfg = builder().dotenv_path(&path).build().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cfg.dotenv_path(), Some(path.as_path()));
+        assert_eq!(
+            cfg.dotenv,
+            [("ORDERS_QUEUE".to_string(), "orders".to_string())]
+        );
+        assert!(builder().build().unwrap().dotenv.is_empty());
+
+        let err = builder().dotenv_path(&path).build().err().unwrap();
+        assert!(
+            err.to_string()
+                .starts_with(&format!("cannot read the .env file {}: ", path.display())),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_build_env_mask() {
+        assert_eq!(builder().build().unwrap().env_mask, DEFAULT_MASK_PATTERNS);
+        let cfg = builder().env_mask(["api_"]).build().unwrap();
+        assert_eq!(cfg.env_mask, ["api_"]);
+    }
+
+    #[test]
+    fn test_build_disk_mount_point() {
+        assert_eq!(builder().build().unwrap().disk_mount_point, None);
+        let cfg = builder().disk_mount_point("/data").build().unwrap();
+        assert_eq!(cfg.disk_mount_point.as_deref(), Some("/data"));
+    }
+
+    #[test]
+    fn test_build_metrics_history_size() {
+        assert_eq!(builder().build().unwrap().metrics_history_size, 60);
+        let cfg = builder().metrics_history_size(5).build().unwrap();
+        assert_eq!(cfg.metrics_history_size, 5);
+        let err = builder().metrics_history_size(0).build().err().unwrap();
+        assert_eq!(err.to_string(), "metrics history size must not be zero");
+    }
+
+    #[test]
+    fn test_build_cpu_usage_window() {
+        assert_eq!(builder().build().unwrap().cpu_usage_window, None);
+        let cfg = builder().cpu_usage_window(5).build().unwrap();
+        assert_eq!(cfg.cpu_usage_window, Some(5));
+        let err = builder().cpu_usage_window(0).build().err().unwrap();
+        assert_eq!(err.to_string(), "cpu usage window must not be zero");
+    }
+
+    #[test]
+    fn test_build_health_status_code() {
+        let cfg = builder()
+            .health_status_code(HealthStatus::Degraded, 503)
+            .build()
+            .unwrap();
+        assert_eq!(
+            cfg.health_status_codes,
+            HashMap::from([(HealthStatus::Degraded, 503)])
+        );
+        let err = builder()
+            .health_status_code(HealthStatus::Down, 99)
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "health status code 99 of Down is not a valid HTTP status"
+        );
+    }
+
+    #[test]
+    fn test_build_shutdown_timeout() {
+        let cfg = builder().build().unwrap();
+        assert_eq!(cfg.shutdown_timeout, Duration::from_secs(10));
+        let cfg = builder()
+            .shutdown_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        assert_eq!(cfg.shutdown_timeout, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_build_ping_response() {
+        assert_eq!(builder().build().unwrap().ping_response, "pong");
+        let cfg = builder()
+            .ping_response(r#"{"status":"UP"}"#)
+            .build()
+            .unwrap();
+        assert_eq!(cfg.ping_response, r#"{"status":"UP"}"#);
+    }
+
+    #[test]
+    fn test_build_base_path() {
+        assert_eq!(builder().build().unwrap().base_path(), "/actuator");
+        for base_path in ["/ops", "/internal/actuator", ""] {
+            let cfg = builder().base_path(base_path).build().unwrap();
+            assert_eq!(cfg.base_path(), base_path);
+        }
+    }
+
+    #[test]
+    fn test_build_invalid_base_path() {
+        for base_path in ["ops", "/ops/", "/"] {
+            let err = builder().base_path(base_path).build().err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("base path {base_path:?} must start with a slash and not end with one")
+            );
+        }
+    }
+
+    #[cfg(feature = "config-json")]
+    #[test]
+    fn test_from_json_file() {
+        let path = std::env::temp_dir().join(format!("actuator-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{
+                "name": "orders",
+                "env": "production",
+                "version": "1.0.0",
+                "port": 8080,
+                "endpoints": ["ping", "health", "threaddump"],
+                "health": { "cache_duration": "30s", "timeout": "1500ms" }
+            }"#,
+        )
+        .unwrap();
+        let cfg = Config::from_json_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cfg.name(), "orders");
+        assert_eq!(cfg.env(), "production");
+        assert_eq!(cfg.version(), "1.0.0");
+        assert_eq!(cfg.port(), 8080);
+        assert_eq!(
+            cfg.endpoints(),
+            [Endpoint::Ping, Endpoint::Health, Endpoint::ThreadDump]
+        );
+        assert_eq!(cfg.health.cache_duration, Duration::from_secs(30));
+        assert_eq!(cfg.health.timeout, Duration::from_millis(1500));
+    }
+
+    #[cfg(feature = "config-json")]
+    #[test]
+    fn test_from_json_defaults() {
+        let cfg = Config::from_json(r#"{"name": "orders", "port": 8080}"#).unwrap();
+        assert_eq!(cfg.env(), "");
+        assert!(cfg.endpoints().is_empty());
+        assert_eq!(cfg.health.timeout, Duration::from_secs(5));
+    }
+
+    #[cfg(feature = "config-json")]
+    #[test]
+    fn test_from_json_invalid() {
+        let err =
+            Config::from_json(r#"{"name": "orders", "port": 8080, "health": {"timeout": "soon"}}"#)
+                .err()
+                .unwrap();
+        assert_eq!(
+            err.to_string(),
+            r#"health.timeout: invalid duration "soon", expected a whole amount of ms, s or m"#
+        );
+        let err = Config::from_json(r#"{"name": "orders"}"#).err().unwrap();
+        assert!(
+            err.to_string()
+                .starts_with("invalid config: missing field `port`")
+        );
+        let err = Config::from_json(r#"{"name": "", "port": 8080}"#)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "name must not be empty");
+        let err = Config::from_json_file("/nonexistent/actuator.json")
+            .err()
+            .unwrap();
+        assert!(!err.to_string().is_empty());
+    }
+
+    #[cfg(all(feature = "config-toml", feature = "config-yaml"))]
+    #[test]
+    fn test_from_toml_and_yaml() {
+        let toml = Config::from_toml_str(
+            r#"
+            name = "orders"
+            env = "production"
+            version = "1.0.0"
+            port = 8080
+            endpoints = ["ping", "health", "threaddump"]
+
+            [health]
+            cache_duration = "30s"
+            timeout = "1500ms"
+            "#,
+        )
+        .unwrap();
+        let yaml = Config::from_yaml_str(
+            "
+            name: orders
+            env: production
+            version: 1.0.0
+            port: 8080
+            endpoints: [ping, health, threaddump]
+            health:
+              cache_duration: 30s
+              timeout: 1500ms
+            ",
+        )
+        .unwrap();
+        for cfg in [toml, yaml] {
+            assert_eq!(cfg.name(), "orders");
+            assert_eq!(cfg.env(), "production");
+            assert_eq!(cfg.version(), "1.0.0");
+            assert_eq!(cfg.port(), 8080);
+            assert_eq!(
+                cfg.endpoints(),
+                [Endpoint::Ping, Endpoint::Health, Endpoint::ThreadDump]
+            );
+            assert_eq!(cfg.health.cache_duration, Duration::from_secs(30));
+            assert_eq!(cfg.health.timeout, Duration::from_millis(1500));
+        }
+    }
+
+    #[cfg(feature = "config-toml")]
+    #[test]
+    fn test_from_toml_invalid() {
+        let err =
+            Config::from_toml_str("name = \"orders\"\nport = 8080\n[health]\ntimeout = \"soon\"")
+                .err()
+                .unwrap();
+        assert_eq!(
+            err.to_string(),
+            r#"health.timeout: invalid duration "soon", expected a whole amount of ms, s or m"#
+        );
+        let err = Config::from_toml_str("name = \"orders\"").err().unwrap();
+        assert!(err.to_string().starts_with("invalid config:"));
+    }
+
+    #[cfg(feature = "config-yaml")]
+    #[test]
+    fn test_from_yaml_invalid() {
+        let err = Config::from_yaml_str("name: orders\nport: 8080\nhealth:\n  timeout: soon")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            r#"health.timeout: invalid duration "soon", expected a whole amount of ms, s or m"#
+        );
+        let err = Config::from_yaml_str("name: orders\nports: 8080")
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("invalid config:"));
+    }
+
+    #[test]
+    fn test_build_empty_name() {
+        let err = builder().name("").build().err().unwrap();
+        assert_eq!(err.to_string(), "name must not be empty");
+    }
+
+    #[test]
+    fn test_build_zero_port() {
+        let err = builder().port(0).build().err().unwrap();
+        assert_eq!(err.to_string(), "port must not be zero");
+    }
+
+    #[test]
+    fn test_build_duplicate_endpoints() {
+        let err = builder()
+            .endpoints([Endpoint::Ping, Endpoint::Health, Endpoint::Ping])
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "endpoint Ping is listed more than once");
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use crate::error::ActuatorError;
+
+/// Parses a whole amount of milliseconds, seconds or minutes such as `500ms`,
+/// `5s` or `2m`.
+pub fn parse_duration(value: &str) -> Result<Duration, ActuatorError> {
+    let invalid = || {
+        ActuatorError::new(format!(
+            "invalid duration {value:?}, expected a whole amount of ms, s or m"
+        ))
+    };
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(digits);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "s" => Ok(Duration::from_secs(amount)),
+        "m" => amount
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("0s").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        for value in [
+            "",
+            "5",
+            "s",
+            "-5s",
+            "1.5s",
+            "5 s",
+            " 5s",
+            "5h",
+            "5S",
+            "5sec",
+            "5s5",
+            "99999999999999999999s",
+            "999999999999999999m",
+        ] {
+            let err = parse_duration(value).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("invalid duration {value:?}, expected a whole amount of ms, s or m")
+            );
+        }
+    }
+}
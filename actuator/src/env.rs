@@ -0,0 +1,206 @@
+//! Build metadata exported by [vergen](https://docs.rs/vergen) as `VERGEN_*`
+//! environment variables. Missing variables resolve to empty strings, or to
+//! `false` for flags.
+
+use std::collections::HashMap;
+use std::env;
+
+/// Prefix of the variables surfaced as extra info.
+const INFO_PREFIX: &str = "ACTUATOR_INFO_";
+
+fn var(key: &str) -> String {
+    env::var(key).unwrap_or_default()
+}
+
+pub(crate) fn git_sha() -> String {
+    var("VERGEN_GIT_SHA")
+}
+
+pub(crate) fn git_branch() -> String {
+    var("VERGEN_GIT_BRANCH")
+}
+
+pub(crate) fn git_describe() -> String {
+    var("VERGEN_GIT_DESCRIBE")
+}
+
+/// Whether the build had uncommitted changes, anything but `true` reads as clean.
+pub(crate) fn git_dirty() -> bool {
+    var("VERGEN_GIT_DIRTY") == "true"
+}
+
+pub(crate) fn git_commit_timestamp() -> String {
+    var("VERGEN_GIT_COMMIT_TIMESTAMP")
+}
+
+pub(crate) fn rustc_semver() -> String {
+    var("VERGEN_RUSTC_SEMVER")
+}
+
+pub(crate) fn rustc_channel() -> String {
+    var("VERGEN_RUSTC_CHANNEL")
+}
+
+/// Cargo profile the application was built with, not exported by vergen itself
+/// so the build script has to set it, e.g. from the `PROFILE` variable.
+pub(crate) fn cargo_profile() -> String {
+    var("VERGEN_CARGO_PROFILE")
+}
+
+pub(crate) fn cargo_opt_level() -> String {
+    var("VERGEN_CARGO_OPT_LEVEL")
+}
+
+pub(crate) fn cargo_target_triple() -> String {
+    var("VERGEN_CARGO_TARGET_TRIPLE")
+}
+
+/// Variables prefixed with `ACTUATOR_INFO_`, such as CI build numbers, keyed by
+/// the rest of their name in lowercase. Variables that are not valid unicode
+/// are skipped.
+pub(crate) fn info_extra() -> HashMap<String, String> {
+    env::vars_os()
+        .filter_map(|(key, value)| {
+            let key = key.to_str()?.strip_prefix(INFO_PREFIX)?;
+            if key.is_empty() {
+                return None;
+            }
+            Some((key.to_lowercase(), value.into_string().ok()?))
+        })
+        .collect()
+}
+
+/// Variables of the process, skipping the ones that are not valid unicode.
+pub(crate) fn vars() -> impl Iterator<Item = (String, String)> {
+    env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+}
+
+/// Serializes the tests reading or writing the process environment.
+#[cfg(test)]
+pub(crate) static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+pub(crate) fn set_var(key: &str, value: impl AsRef<std::ffi::OsStr>) {
+    // SAFETY: callers hold `ENV_LOCK`, so no other test touches the environment concurrently.
+    unsafe { env::set_var(key, value) }
+}
+
+#[cfg(test)]
+pub(crate) fn remove_var(key: &str) {
+    // SAFETY: callers hold `ENV_LOCK`, so no other test touches the environment concurrently.
+    unsafe { env::remove_var(key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_sha() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_GIT_SHA", "8f3c2a1");
+        assert_eq!(git_sha(), "8f3c2a1");
+        remove_var("VERGEN_GIT_SHA");
+        assert_eq!(git_sha(), "");
+    }
+
+    #[test]
+    fn test_git_branch() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_GIT_BRANCH", "main");
+        assert_eq!(git_branch(), "main");
+        remove_var("VERGEN_GIT_BRANCH");
+        assert_eq!(git_branch(), "");
+    }
+
+    #[test]
+    fn test_git_describe() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_GIT_DESCRIBE", "v1.2.3-4-gabc123");
+        assert_eq!(git_describe(), "v1.2.3-4-gabc123");
+        remove_var("VERGEN_GIT_DESCRIBE");
+        assert_eq!(git_describe(), "");
+    }
+
+    #[test]
+    fn test_git_dirty() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_GIT_DIRTY", "true");
+        assert!(git_dirty());
+        set_var("VERGEN_GIT_DIRTY", "false");
+        assert!(!git_dirty());
+        remove_var("VERGEN_GIT_DIRTY");
+        assert!(!git_dirty());
+    }
+
+    #[test]
+    fn test_git_commit_timestamp() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_GIT_COMMIT_TIMESTAMP", "2024-01-01T00:00:00Z");
+        assert_eq!(git_commit_timestamp(), "2024-01-01T00:00:00Z");
+        remove_var("VERGEN_GIT_COMMIT_TIMESTAMP");
+        assert_eq!(git_commit_timestamp(), "");
+    }
+
+    #[test]
+    fn test_rustc_semver() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_RUSTC_SEMVER", "1.85.0");
+        assert_eq!(rustc_semver(), "1.85.0");
+        remove_var("VERGEN_RUSTC_SEMVER");
+        assert_eq!(rustc_semver(), "");
+    }
+
+    #[test]
+    fn test_rustc_channel() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_RUSTC_CHANNEL", "stable");
+        assert_eq!(rustc_channel(), "stable");
+        remove_var("VERGEN_RUSTC_CHANNEL");
+        assert_eq!(rustc_channel(), "");
+    }
+
+    #[test]
+    fn test_cargo_profile() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_CARGO_PROFILE", "release");
+        assert_eq!(cargo_profile(), "release");
+        remove_var("VERGEN_CARGO_PROFILE");
+        assert_eq!(cargo_profile(), "");
+    }
+
+    #[test]
+    fn test_cargo_opt_level() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_CARGO_OPT_LEVEL", "3");
+        assert_eq!(cargo_opt_level(), "3");
+        remove_var("VERGEN_CARGO_OPT_LEVEL");
+        assert_eq!(cargo_opt_level(), "");
+    }
+
+    #[test]
+    fn test_cargo_target_triple() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("VERGEN_CARGO_TARGET_TRIPLE", "x86_64-unknown-linux-gnu");
+        assert_eq!(cargo_target_triple(), "x86_64-unknown-linux-gnu");
+        remove_var("VERGEN_CARGO_TARGET_TRIPLE");
+        assert_eq!(cargo_target_triple(), "");
+    }
+
+    #[test]
+    fn test_info_extra() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        set_var("ACTUATOR_INFO_BUILD_NUMBER", "42");
+        set_var("ACTUATOR_INFO_IMAGE_TAG", "orders:1.0.0");
+        set_var("ACTUATOR_INFO_", "ignored");
+        let extra = info_extra();
+        assert_eq!(extra["build_number"], "42");
+        assert_eq!(extra["image_tag"], "orders:1.0.0");
+        assert!(!extra.contains_key(""));
+        remove_var("ACTUATOR_INFO_BUILD_NUMBER");
+        remove_var("ACTUATOR_INFO_IMAGE_TAG");
+        remove_var("ACTUATOR_INFO_");
+        assert!(info_extra().is_empty());
+    }
+}
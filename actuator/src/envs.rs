@@ -0,0 +1,253 @@
+//! Environment variables served by the env endpoint.
+
+use std::collections::BTreeMap;
+
+/// Patterns masked by default, matching the usual names of secrets.
+pub(crate) const DEFAULT_MASK_PATTERNS: [&str; 5] =
+    ["password", "secret", "token", "key", "credential"];
+
+/// Value served in place of a masked variable.
+pub(crate) const MASK: &str = "******";
+
+/// Where an environment variable served by the env endpoint was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum EnvSource {
+    /// The environment of the process.
+    Process,
+    /// A `.env` file.
+    DotEnv,
+}
+
+/// Environment variable served by the env endpoint along with where it was
+/// read from, its value masked like the plain ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EnvVar {
+    pub(crate) value: String,
+    pub(crate) source: EnvSource,
+}
+
+impl EnvVar {
+    /// Value of the variable, `******` when masked.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Where the variable was read from.
+    pub fn source(&self) -> EnvSource {
+        self.source
+    }
+}
+
+/// Collects the variables whose name starts with the prefix, all of them
+/// without one, masking the values of the ones whose name contains any of the
+/// patterns, ignoring case. The variables are sorted by name.
+pub(crate) fn collect(
+    vars: impl IntoIterator<Item = (String, String)>,
+    prefix: Option<&str>,
+    mask_patterns: &[String],
+) -> BTreeMap<String, String> {
+    let mask_patterns: Vec<_> = mask_patterns.iter().map(|p| p.to_lowercase()).collect();
+    vars.into_iter()
+        .filter(|(key, _)| prefix.is_none_or(|prefix| key.starts_with(prefix)))
+        .map(|(key, value)| {
+            let lower = key.to_lowercase();
+            if mask_patterns.iter().any(|pattern| lower.contains(pattern)) {
+                (key, MASK.to_string())
+            } else {
+                (key, value)
+            }
+        })
+        .collect()
+}
+
+/// Collects the variables of the process and of a `.env` file like [`collect`]
+/// does, attributing each to its source. The process ones win over the file
+/// ones of the same name unless `dotenv_overrides`.
+pub(crate) fn collect_detailed(
+    process: impl IntoIterator<Item = (String, String)>,
+    dotenv: impl IntoIterator<Item = (String, String)>,
+    dotenv_overrides: bool,
+    prefix: Option<&str>,
+    mask_patterns: &[String],
+) -> BTreeMap<String, EnvVar> {
+    let attribute = |vars: BTreeMap<String, String>, source| {
+        vars.into_iter()
+            .map(move |(key, value)| (key, EnvVar { value, source }))
+    };
+    let dotenv = attribute(collect(dotenv, prefix, mask_patterns), EnvSource::DotEnv);
+    let process = attribute(collect(process, prefix, mask_patterns), EnvSource::Process);
+    if dotenv_overrides {
+        process.chain(dotenv).collect()
+    } else {
+        dotenv.chain(process).collect()
+    }
+}
+
+/// Parses the `KEY=value` lines of a `.env` file, skipping blank lines and
+/// `#` comments. Keys may be preceded by `export` and values wrapped in single
+/// or double quotes.
+pub(crate) fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = ['"', '\'']
+                .into_iter()
+                .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Vec<(String, String)> {
+        [
+            ("AWS_SECRET_ACCESS_KEY", "wJalrXUtnFEMI"),
+            ("DATABASE_PASSWORD", "hunter2"),
+            ("GITHUB_TOKEN", "ghp_123"),
+            ("HOME", "/home/app"),
+            ("PORT", "8080"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_collect_default_patterns() {
+        let patterns: Vec<_> = DEFAULT_MASK_PATTERNS.map(String::from).to_vec();
+        let envs = collect(vars(), None, &patterns);
+        assert_eq!(envs["AWS_SECRET_ACCESS_KEY"], MASK);
+        assert_eq!(envs["DATABASE_PASSWORD"], MASK);
+        assert_eq!(envs["GITHUB_TOKEN"], MASK);
+        assert_eq!(envs["HOME"], "/home/app");
+        assert_eq!(envs["PORT"], "8080");
+    }
+
+    #[test]
+    fn test_collect_custom_patterns() {
+        let envs = collect(vars(), None, &["Port".to_string()]);
+        assert_eq!(envs["PORT"], MASK);
+        assert_eq!(envs["DATABASE_PASSWORD"], "hunter2");
+    }
+
+    #[test]
+    fn test_collect_no_patterns() {
+        let envs = collect(vars(), None, &[]);
+        assert_eq!(envs["GITHUB_TOKEN"], "ghp_123");
+    }
+
+    #[test]
+    fn test_collect_prefix() {
+        let vars = [
+            ("APP_PORT", "8080"),
+            ("APP_TOKEN", "abc"),
+            ("HOME", "/home/app"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+        let envs = collect(vars.clone(), Some("APP_"), &["token".to_string()]);
+        assert_eq!(envs.len(), 2);
+        assert_eq!(envs["APP_PORT"], "8080");
+        assert_eq!(envs["APP_TOKEN"], MASK);
+        assert!(!envs.contains_key("HOME"));
+        assert_eq!(collect(vars, None, &[]).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_dotenv() {
+        let vars = parse_dotenv(
+            "\
+# database
+DATABASE_URL=postgres://localhost/orders
+
+export PORT = 9090
+GREETING=\"hello world\"
+QUOTED='single'
+MALFORMED
+",
+        );
+        assert_eq!(
+            vars,
+            [
+                ("DATABASE_URL", "postgres://localhost/orders"),
+                ("PORT", "9090"),
+                ("GREETING", "hello world"),
+                ("QUOTED", "single"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_collect_detailed() {
+        // The variables of a mock `.env` file.
+        let dotenv = [
+            ("PORT", "9090"),
+            ("DATABASE_URL", "postgres://localhost"),
+            ("API_TOKEN", "abc"),
+        ]
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+        let envs = collect_detailed(vars(), dotenv.clone(), false, None, &["token".to_string()]);
+        assert_eq!(envs["PORT"].value(), "8080");
+        assert_eq!(envs["PORT"].source(), EnvSource::Process);
+        assert_eq!(envs["DATABASE_URL"].value(), "postgres://localhost");
+        assert_eq!(envs["DATABASE_URL"].source(), EnvSource::DotEnv);
+        assert_eq!(envs["API_TOKEN"].value(), MASK);
+        assert_eq!(envs["API_TOKEN"].source(), EnvSource::DotEnv);
+        assert_eq!(envs["HOME"].source(), EnvSource::Process);
+        assert_eq!(envs.len(), 7);
+
+        let envs = collect_detailed(vars(), dotenv, true, None, &[]);
+        assert_eq!(envs["PORT"].value(), "9090");
+        assert_eq!(envs["PORT"].source(), EnvSource::DotEnv);
+        assert_eq!(envs["HOME"].source(), EnvSource::Process);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_env_var() {
+        let envs = collect_detailed(
+            vars(),
+            [("API_URL".to_string(), "http://api".to_string())],
+            false,
+            Some("API"),
+            &[],
+        );
+        assert_eq!(
+            serde_json::to_value(envs).unwrap(),
+            serde_json::json!({"API_URL": {"value": "http://api", "source": "dot_env"}})
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_sorted() {
+        let mut vars = vars();
+        vars.reverse();
+        vars.push(("APP_NAME".to_string(), "orders".to_string()));
+        let json = serde_json::to_string(&collect(vars, None, &[])).unwrap();
+        let positions: Vec<_> = [
+            "APP_NAME",
+            "AWS_SECRET_ACCESS_KEY",
+            "DATABASE_PASSWORD",
+            "GITHUB_TOKEN",
+            "HOME",
+            "PORT",
+        ]
+        .iter()
+        .map(|key| json.find(&format!("\"{key}\":")).unwrap())
+        .collect();
+        assert!(positions.is_sorted(), "{json}");
+    }
+}
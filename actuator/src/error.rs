@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Error reported by the actuator and by the health checks registered with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActuatorError {
+    pub(crate) details: String,
+}
+
+impl ActuatorError {
+    /// Creates an error with the given message.
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self {
+            details: msg.into(),
+        }
+    }
+}
+
+impl Display for ActuatorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.details)
+    }
+}
+
+impl Error for ActuatorError {}
+
+impl From<Box<dyn Error>> for ActuatorError {
+    fn from(err: Box<dyn Error>) -> Self {
+        Self::new(err.to_string())
+    }
+}
+
+impl From<io::Error> for ActuatorError {
+    fn from(err: io::Error) -> Self {
+        Self::new(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            ActuatorError::new("db unreachable").to_string(),
+            "db unreachable"
+        );
+    }
+
+    #[test]
+    fn test_from_boxed_error() {
+        let err: Box<dyn Error> = "invalid response".into();
+        assert_eq!(ActuatorError::from(err).to_string(), "invalid response");
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        let err = io::Error::new(io::ErrorKind::ConnectionRefused, "connection refused");
+        assert_eq!(ActuatorError::from(err).to_string(), "connection refused");
+    }
+
+    #[test]
+    fn test_question_mark_conversion() {
+        fn check() -> Result<(), ActuatorError> {
+            Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out"))?
+        }
+        assert_eq!(check().err().unwrap().to_string(), "read timed out");
+    }
+}
@@ -0,0 +1,2268 @@
+use std::any::Any;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::future::{BoxFuture, join_all};
+use futures::{FutureExt, TryFutureExt};
+use tokio::sync::Semaphore;
+
+use crate::error::ActuatorError;
+
+/// Plain function run to check the health of a single dependency, succeeding
+/// with `()` or with [`HealthDetails`].
+pub type HealthCheckFn<E, T = ()> = fn() -> BoxFuture<'static, Result<T, E>>;
+
+/// Check of a single dependency, possibly capturing state such as a connection
+/// pool.
+pub type HealthCheck<E> =
+    Arc<dyn Fn() -> BoxFuture<'static, Result<HealthDetails, E>> + Send + Sync>;
+
+/// Adapts a plain function into a [`HealthCheck`].
+pub fn health_check_fn<E: 'static, T: Into<HealthDetails> + 'static>(
+    func: HealthCheckFn<E, T>,
+) -> HealthCheck<E> {
+    Arc::new(move || Box::pin(func().map_ok(Into::into)))
+}
+
+/// Details a successful check reports about the dependency, such as the size
+/// of a connection pool, serialized along its outcome.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthDetails {
+    #[cfg(feature = "serde")]
+    pub(crate) details: HashMap<String, serde_json::Value>,
+}
+
+impl HealthDetails {
+    /// Creates empty details.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a detail, replacing the one under the same key if any.
+    #[cfg(feature = "serde")]
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl From<()> for HealthDetails {
+    fn from(_: ()) -> Self {
+        Self::default()
+    }
+}
+
+/// Built-in check failing while the memory used on the host, in percent of the
+/// total as reported by [`Metrics`](crate::Metrics), exceeds the threshold,
+/// and while the host reports no memory.
+///
+/// It is turned into a [`HealthCheck`] with `into()`, which measures the usage
+/// on the blocking thread pool of Tokio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryHealthCheck {
+    pub(crate) threshold_percent: f32,
+}
+
+impl MemoryHealthCheck {
+    /// Creates the check with the highest memory usage still healthy.
+    pub fn new(threshold_percent: f32) -> Self {
+        Self { threshold_percent }
+    }
+
+    /// Measures the memory usage, reporting it as the `memory_usage_percent`
+    /// detail on success. Blocks while the memory is refreshed.
+    pub fn check(&self) -> Result<HealthDetails, ActuatorError> {
+        let usage = crate::metrics::memory_usage_percent()?;
+        if usage > self.threshold_percent {
+            return Err(ActuatorError::new(format!(
+                "memory usage of {usage:.1}% exceeds the threshold of {}%",
+                self.threshold_percent
+            )));
+        }
+        #[cfg(feature = "serde")]
+        let details = HealthDetails::new().with("memory_usage_percent", usage);
+        #[cfg(not(feature = "serde"))]
+        let details = HealthDetails::new();
+        Ok(details)
+    }
+}
+
+impl<E: From<ActuatorError> + 'static> From<MemoryHealthCheck> for HealthCheck<E> {
+    fn from(check: MemoryHealthCheck) -> Self {
+        Arc::new(move || {
+            Box::pin(async move {
+                match tokio::task::spawn_blocking(move || check.check()).await {
+                    Ok(result) => result.map_err(E::from),
+                    Err(err) => std::panic::resume_unwind(err.into_panic()),
+                }
+            })
+        })
+    }
+}
+
+/// Built-in check failing while the space available on the disk holding `path`
+/// falls below `min_free_bytes`.
+///
+/// It is turned into a [`HealthCheck`] with `into()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskSpaceHealthCheck {
+    pub(crate) path: PathBuf,
+    pub(crate) min_free_bytes: u64,
+}
+
+impl DiskSpaceHealthCheck {
+    /// Creates the check of the disk holding `path`, which must exist.
+    pub fn new(path: impl Into<PathBuf>, min_free_bytes: u64) -> Self {
+        Self {
+            path: path.into(),
+            min_free_bytes,
+        }
+    }
+
+    /// Measures the available space, reporting the `mount_point` and the
+    /// `available_space` in bytes as details on success.
+    pub fn check(&self) -> Result<HealthDetails, ActuatorError> {
+        let path = self.path.canonicalize().map_err(|err| {
+            ActuatorError::new(format!("cannot resolve {}: {err}", self.path.display()))
+        })?;
+        let disks = crate::metrics::disks();
+        let disk = crate::metrics::disk_of(&path, &disks).ok_or_else(|| {
+            ActuatorError::new(format!("no disk is mounted at {}", path.display()))
+        })?;
+        if disk.available_space < self.min_free_bytes {
+            return Err(ActuatorError::new(format!(
+                "{} bytes available on {}, expected at least {}",
+                disk.available_space, disk.mount_point, self.min_free_bytes
+            )));
+        }
+        #[cfg(feature = "serde")]
+        let details = HealthDetails::new()
+            .with("mount_point", disk.mount_point.as_str())
+            .with("available_space", disk.available_space);
+        #[cfg(not(feature = "serde"))]
+        let details = HealthDetails::new();
+        Ok(details)
+    }
+}
+
+impl<E: From<ActuatorError> + 'static> From<DiskSpaceHealthCheck> for HealthCheck<E> {
+    fn from(check: DiskSpaceHealthCheck) -> Self {
+        Arc::new(move || {
+            let check = check.clone();
+            Box::pin(async move { check.check().map_err(E::from) })
+        })
+    }
+}
+
+/// Built-in check failing unless a TCP connection to `addr`, a `host:port`
+/// pair, opens within `timeout`. The connection is closed right away.
+///
+/// It is turned into a [`HealthCheck`] with `into()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpHealthCheck {
+    pub(crate) addr: String,
+    pub(crate) timeout: Duration,
+}
+
+impl TcpHealthCheck {
+    /// Creates the check of the address, resolved on every check.
+    pub fn new(addr: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            addr: addr.into(),
+            timeout,
+        }
+    }
+
+    /// Opens the connection, reporting the `peer` address connected to as
+    /// details on success. Must be awaited within a Tokio runtime.
+    pub async fn check(&self) -> Result<HealthDetails, ActuatorError> {
+        let connect = tokio::net::TcpStream::connect(self.addr.as_str());
+        let stream = tokio::time::timeout(self.timeout, connect)
+            .await
+            .map_err(|_| {
+                ActuatorError::new(format!(
+                    "connecting to {} timed out after {:?}",
+                    self.addr, self.timeout
+                ))
+            })?
+            .map_err(|err| ActuatorError::new(format!("cannot connect to {}: {err}", self.addr)))?;
+        #[cfg(feature = "serde")]
+        let details = match stream.peer_addr() {
+            Ok(peer) => HealthDetails::new().with("peer", peer.to_string()),
+            Err(_) => HealthDetails::new(),
+        };
+        #[cfg(not(feature = "serde"))]
+        let details = {
+            drop(stream);
+            HealthDetails::new()
+        };
+        Ok(details)
+    }
+}
+
+impl<E: From<ActuatorError> + 'static> From<TcpHealthCheck> for HealthCheck<E> {
+    fn from(check: TcpHealthCheck) -> Self {
+        Arc::new(move || {
+            let check = check.clone();
+            Box::pin(async move { check.check().await.map_err(E::from) })
+        })
+    }
+}
+
+/// Built-in check failing unless a GET of `url` answers with
+/// `expected_status` within `timeout`. Only plain HTTP is supported.
+///
+/// It is turned into a [`HealthCheck`] with `into()`.
+#[cfg(feature = "http-check")]
+#[derive(Debug, Clone)]
+pub struct HttpHealthCheck {
+    pub(crate) url: String,
+    pub(crate) expected_status: u16,
+    pub(crate) timeout: Duration,
+    pub(crate) client: reqwest::Client,
+}
+
+#[cfg(feature = "http-check")]
+impl HttpHealthCheck {
+    /// Creates the check of the URL, reusing its connections across checks.
+    pub fn new(url: impl Into<String>, expected_status: u16, timeout: Duration) -> Self {
+        Self {
+            url: url.into(),
+            expected_status,
+            timeout,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends the request, reporting the `status` answered as details on
+    /// success. Must be awaited within a Tokio runtime.
+    pub async fn check(&self) -> Result<HealthDetails, ActuatorError> {
+        let response = self
+            .client
+            .get(self.url.as_str())
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|err| {
+                if err.is_timeout() {
+                    ActuatorError::new(format!(
+                        "request to {} timed out after {:?}",
+                        self.url, self.timeout
+                    ))
+                } else {
+                    ActuatorError::new(format!("request to {} failed: {err}", self.url))
+                }
+            })?;
+        let status = response.status().as_u16();
+        if status != self.expected_status {
+            return Err(ActuatorError::new(format!(
+                "{} answered with status {status}, expected {}",
+                self.url, self.expected_status
+            )));
+        }
+        #[cfg(feature = "serde")]
+        return Ok(HealthDetails::new().with("status", status));
+        #[cfg(not(feature = "serde"))]
+        Ok(HealthDetails::new())
+    }
+}
+
+#[cfg(feature = "http-check")]
+impl<E: From<ActuatorError> + 'static> From<HttpHealthCheck> for HealthCheck<E> {
+    fn from(check: HttpHealthCheck) -> Self {
+        Arc::new(move || {
+            let check = check.clone();
+            Box::pin(async move { check.check().await.map_err(E::from) })
+        })
+    }
+}
+
+/// Aggregate status of the evaluated health checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
+pub enum HealthStatus {
+    /// Every check succeeded.
+    Up,
+    /// Only non-mandatory checks failed, under [`OptionalFailurePolicy::Degrade`].
+    Degraded,
+    /// At least one mandatory check failed.
+    Down,
+}
+
+/// How the checks of an evaluation are run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// The checks run at once, up to the configured maximum concurrency.
+    #[default]
+    Concurrent,
+    /// The checks run one at a time, in registration order, so a shared
+    /// dependency is not hit by all of them at once.
+    Sequential,
+}
+
+/// How the failures of the non-mandatory checks affect the aggregate status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OptionalFailurePolicy {
+    /// The status stays up.
+    Ignore,
+    /// The status is degraded, unless a mandatory check failed.
+    #[default]
+    Degrade,
+}
+
+/// Built-in group a health check contributes to, the probes in the Kubernetes
+/// sense. Checks can also be tagged with groups of any other name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HealthGroup {
+    /// Whether the service is running and should not be restarted.
+    Liveness,
+    /// Whether the service can accept traffic.
+    Readiness,
+}
+
+impl HealthGroup {
+    /// Name the checks are tagged with, `liveness` or `readiness`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HealthGroup::Liveness => "liveness",
+            HealthGroup::Readiness => "readiness",
+        }
+    }
+}
+
+impl From<HealthGroup> for String {
+    fn from(group: HealthGroup) -> Self {
+        group.name().to_string()
+    }
+}
+
+/// A named health check failing with errors of type `E`.
+///
+/// A failing mandatory check marks the whole service unhealthy, a failing
+/// non-mandatory one is only reported.
+///
+/// The check contributes to the groups it is tagged with by name, such as the
+/// [`HealthGroup`] probes.
+///
+/// A `timeout` overrides the one of the [`HealthConfig`] for this check, and
+/// applies to each attempt. A failing check is retried up to `retries` times,
+/// `retry_backoff` apart, before its last error is reported.
+///
+/// A check depending on the keys of other checks runs after them, and is
+/// skipped when any of them failed or was skipped, e.g. a query after the
+/// connection check.
+///
+/// A `cache_duration` overrides the ones of the [`HealthConfig`] for this
+/// check.
+pub struct HealthChecker<E = ActuatorError> {
+    pub(crate) key: String,
+    pub(crate) is_mandatory: bool,
+    pub(crate) groups: BTreeSet<String>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retries: u32,
+    pub(crate) retry_backoff: Duration,
+    pub(crate) cache_duration: Option<Duration>,
+    pub(crate) depends_on: Vec<String>,
+    pub(crate) func: HealthCheck<E>,
+}
+
+impl<E> HealthChecker<E> {
+    /// Creates a mandatory check under the key, in no group, bounded by the
+    /// timeout of the [`HealthConfig`] and not retried.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use futures::FutureExt;
+    /// use rust_actuator::{ActuatorError, HealthChecker, HealthGroup, health_check_fn};
+    ///
+    /// let checker: HealthChecker = HealthChecker::new(
+    ///     "db",
+    ///     health_check_fn(|| async { Ok::<_, ActuatorError>(()) }.boxed()),
+    /// )
+    /// .mandatory(false)
+    /// .timeout(Duration::from_secs(1))
+    /// .group(HealthGroup::Readiness);
+    /// ```
+    pub fn new(key: impl Into<String>, func: impl Into<HealthCheck<E>>) -> Self {
+        Self {
+            key: key.into(),
+            is_mandatory: true,
+            groups: BTreeSet::new(),
+            timeout: None,
+            retries: 0,
+            retry_backoff: Duration::ZERO,
+            cache_duration: None,
+            depends_on: Vec::new(),
+            func: func.into(),
+        }
+    }
+
+    /// Sets whether the failure of the check reports the service down.
+    pub fn mandatory(mut self, is_mandatory: bool) -> Self {
+        self.is_mandatory = is_mandatory;
+        self
+    }
+
+    /// Sets how long each attempt of the check may run, overriding the timeout
+    /// of the [`HealthConfig`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Tags the check with the group, such as a [`HealthGroup`].
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.groups.insert(group.into());
+        self
+    }
+
+    /// Retries the failing check up to `retries` times, `backoff` apart.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Sets how long a success of the check is served from cache, overriding
+    /// the durations of the [`HealthConfig`].
+    pub fn cache_duration(mut self, cache_duration: Duration) -> Self {
+        self.cache_duration = Some(cache_duration);
+        self
+    }
+
+    /// Runs the check after the one under the key, skipping it when that one
+    /// failed.
+    pub fn depends_on(mut self, key: impl Into<String>) -> Self {
+        self.depends_on.push(key.into());
+        self
+    }
+
+    /// Whether the check is evaluated with the group, every check is without one.
+    fn in_group(&self, group: Option<&str>) -> bool {
+        group.is_none_or(|group| self.groups.contains(group))
+    }
+}
+
+impl<E> Clone for HealthChecker<E> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            is_mandatory: self.is_mandatory,
+            groups: self.groups.clone(),
+            timeout: self.timeout,
+            retries: self.retries,
+            retry_backoff: self.retry_backoff,
+            cache_duration: self.cache_duration,
+            depends_on: self.depends_on.clone(),
+            func: self.func.clone(),
+        }
+    }
+}
+
+/// Health checks the actuator evaluates.
+///
+/// A successful check is served from cache for its own cache duration if set,
+/// else the one of the group evaluated if set, else `cache_duration`, so each
+/// check refreshes on its own schedule. Failures are never cached, and a check
+/// depending on a failed one is skipped even while cached. A check still
+/// running after `timeout` is reported as failed. In `strict` mode an
+/// evaluation without any check reports [`HealthStatus::Down`].
+pub struct HealthConfig<E = ActuatorError> {
+    pub(crate) checkers: Vec<HealthChecker<E>>,
+    pub(crate) cache_duration: Duration,
+    pub(crate) group_cache_durations: HashMap<String, Duration>,
+    pub(crate) timeout: Duration,
+    pub(crate) strict: bool,
+    pub(crate) execution: ExecutionMode,
+    pub(crate) optional_failure_policy: OptionalFailurePolicy,
+    pub(crate) max_concurrency: Option<usize>,
+    pub(crate) startup_grace: Duration,
+}
+
+impl<E> Clone for HealthConfig<E> {
+    fn clone(&self) -> Self {
+        Self {
+            checkers: self.checkers.clone(),
+            cache_duration: self.cache_duration,
+            group_cache_durations: self.group_cache_durations.clone(),
+            timeout: self.timeout,
+            strict: self.strict,
+            execution: self.execution,
+            optional_failure_policy: self.optional_failure_policy,
+            max_concurrency: self.max_concurrency,
+            startup_grace: self.startup_grace,
+        }
+    }
+}
+
+impl<E> HealthConfig<E> {
+    /// Adds a health check.
+    pub fn checker(mut self, checker: HealthChecker<E>) -> Self {
+        self.checkers.push(checker);
+        self
+    }
+
+    /// Sets how long a successful check is served from cache.
+    pub fn cache_duration(mut self, cache_duration: Duration) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    /// Sets how long a successful check is served from cache when evaluating
+    /// the group, overriding the `cache_duration` for it.
+    pub fn group_cache_duration(
+        mut self,
+        group: impl Into<String>,
+        cache_duration: Duration,
+    ) -> Self {
+        self.group_cache_durations
+            .insert(group.into(), cache_duration);
+        self
+    }
+
+    /// Sets how long a single check may run before it is reported as failed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how the checks of an evaluation are run, concurrently by default.
+    pub fn execution(mut self, execution: ExecutionMode) -> Self {
+        self.execution = execution;
+        self
+    }
+
+    /// Sets how the failures of the non-mandatory checks affect the aggregate
+    /// status, they degrade it by default.
+    pub fn optional_failure_policy(mut self, policy: OptionalFailurePolicy) -> Self {
+        self.optional_failure_policy = policy;
+        self
+    }
+
+    /// Limits how many checks run at once in [`ExecutionMode::Concurrent`], at
+    /// least one. Unbounded by default.
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        self.max_concurrency = Some(limit.max(1));
+        self
+    }
+
+    /// Sets whether evaluating no check at all reports the service down, to catch
+    /// forgotten registrations. Lenient by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets how long after startup the liveness is reported up without running
+    /// any check, so a slow startup does not get the service restarted. None by
+    /// default.
+    pub fn startup_grace(mut self, startup_grace: Duration) -> Self {
+        self.startup_grace = startup_grace;
+        self
+    }
+}
+
+impl<E> Default for HealthConfig<E> {
+    fn default() -> Self {
+        Self {
+            checkers: Vec::new(),
+            cache_duration: Duration::ZERO,
+            group_cache_durations: HashMap::new(),
+            timeout: Duration::from_secs(5),
+            strict: false,
+            execution: ExecutionMode::Concurrent,
+            optional_failure_policy: OptionalFailurePolicy::Degrade,
+            max_concurrency: None,
+            startup_grace: Duration::ZERO,
+        }
+    }
+}
+
+/// Outcome of a single health check, and how long the check took.
+///
+/// A check skipped because a dependency failed is reported as failed, and
+/// `skipped`. When serialized, the duration is rendered as whole milliseconds
+/// under `duration_ms`, the details reported on success, if any, under
+/// `details`, and `skipped` only when set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HealthInfo {
+    pub(crate) key: String,
+    pub(crate) is_mandatory: bool,
+    pub(crate) success: bool,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "std::ops::Not::not"))]
+    pub(crate) skipped: bool,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) error: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "duration_ms", serialize_with = "serialize_millis")
+    )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::schema::whole_units")
+    )]
+    pub(crate) duration: Duration,
+    #[cfg(feature = "serde")]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) details: HashMap<String, serde_json::Value>,
+}
+
+impl HealthInfo {
+    /// Key of the checker.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Whether the failure of the check reports the service down.
+    pub fn is_mandatory(&self) -> bool {
+        self.is_mandatory
+    }
+
+    /// Whether the check succeeded.
+    pub fn success(&self) -> bool {
+        self.success
+    }
+
+    /// Whether the check was skipped because a dependency failed.
+    pub fn skipped(&self) -> bool {
+        self.skipped
+    }
+
+    /// Error the check failed with, if it did.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// How long the check took.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Details the check reported on success.
+    #[cfg(feature = "serde")]
+    pub fn details(&self) -> &HashMap<String, serde_json::Value> {
+        &self.details
+    }
+
+    fn new<E>(
+        checker: &HealthChecker<E>,
+        result: Result<HealthDetails, String>,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            key: checker.key.clone(),
+            is_mandatory: checker.is_mandatory,
+            success: result.is_ok(),
+            skipped: false,
+            #[cfg(feature = "serde")]
+            details: result
+                .as_ref()
+                .map(|details| details.details.clone())
+                .unwrap_or_default(),
+            error: result.err(),
+            duration,
+        }
+    }
+
+    /// Outcome of a check that was not run, for the reason given.
+    fn not_run<E>(checker: &HealthChecker<E>, reason: String) -> Self {
+        Self {
+            key: checker.key.clone(),
+            is_mandatory: checker.is_mandatory,
+            success: false,
+            skipped: true,
+            #[cfg(feature = "serde")]
+            details: HashMap::new(),
+            error: Some(reason),
+            duration: Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis().try_into().unwrap_or(u64::MAX))
+}
+
+/// Outcome of an evaluation of the health checks, possibly served from cache.
+///
+/// When serialized, `checked_at` is rendered as RFC 3339.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HealthReport {
+    pub(crate) status: HealthStatus,
+    pub(crate) checks: Arc<HashMap<String, HealthInfo>>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_rfc3339"))]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "crate::schema::date_time"))]
+    pub(crate) checked_at: SystemTime,
+}
+
+impl HealthReport {
+    /// Aggregate status of the checks.
+    pub fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    /// Outcome of each check keyed by the checker key.
+    pub fn checks(&self) -> &HashMap<String, HealthInfo> {
+        &self.checks
+    }
+
+    /// When the checks were run.
+    pub fn checked_at(&self) -> SystemTime {
+        self.checked_at
+    }
+
+    /// Renders the report in the shape of the Spring Boot health endpoint, each
+    /// check as a component with the error and details of a failure under
+    /// `details`. Degraded is reported as `OUT_OF_SERVICE`.
+    #[cfg(feature = "spring-compat")]
+    pub(crate) fn to_spring(&self) -> serde_json::Value {
+        let status = match self.status {
+            HealthStatus::Up => "UP",
+            HealthStatus::Degraded => "OUT_OF_SERVICE",
+            HealthStatus::Down => "DOWN",
+        };
+        let components: serde_json::Map<_, _> = self
+            .checks
+            .iter()
+            .map(|(key, info)| {
+                let mut component = serde_json::Map::new();
+                let status = if info.success { "UP" } else { "DOWN" };
+                component.insert("status".to_string(), status.into());
+                let mut details: serde_json::Map<_, _> = info
+                    .details
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                if let Some(error) = &info.error {
+                    details.insert("error".to_string(), error.as_str().into());
+                }
+                if !details.is_empty() {
+                    component.insert("details".to_string(), details.into());
+                }
+                (key.clone(), component.into())
+            })
+            .collect();
+        serde_json::json!({ "status": status, "components": components })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_rfc3339<S: serde::Serializer>(
+    stamp: &SystemTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&humantime::format_rfc3339(*stamp))
+}
+
+/// Source of the current time the cached outcomes are stamped and expired with.
+pub(crate) trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// Clock reading the system time.
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Health checks and the cached outcome of each, shared by the clones of an
+/// actuator.
+pub(crate) struct InnerHealth<E> {
+    cfg: RwLock<HealthConfig<E>>,
+    /// Bumped whenever the checks change, so evaluations of the previous set are
+    /// not cached.
+    generation: AtomicU64,
+    /// Cleared to report readiness and overall health down whatever the checks
+    /// say, e.g. to drain traffic before a shutdown.
+    ready: AtomicBool,
+    /// Last successful outcome of each check by key, and when it was checked.
+    results: RwLock<HashMap<String, (HealthInfo, SystemTime)>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<E: Error + Send + 'static> InnerHealth<E> {
+    pub(crate) fn new(cfg: HealthConfig<E>) -> Self {
+        Self::with_clock(cfg, Arc::new(SystemClock))
+    }
+
+    pub(crate) fn with_clock(cfg: HealthConfig<E>, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            cfg: RwLock::new(cfg),
+            generation: AtomicU64::new(0),
+            ready: AtomicBool::new(true),
+            results: RwLock::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Evaluates the checks of the group, all of them without one, running only
+    /// the ones not served from cache.
+    pub(crate) async fn get(&self, group: Option<&str>) -> HealthReport {
+        traced(group, async {
+            let (cfg, generation) = self.snapshot();
+            let cached = self.get_from_cache(&cfg, group);
+            #[cfg(feature = "tracing")]
+            if cfg
+                .checkers
+                .iter()
+                .filter(|checker| checker.in_group(group))
+                .all(|checker| cached.contains_key(&checker.key))
+            {
+                tracing::debug!("health cache hit");
+            } else {
+                tracing::debug!("health cache miss");
+            }
+            let report = self
+                .get_health_and_cache_if_success(&cfg, group, generation, cached)
+                .await;
+            self.gate(group, report)
+        })
+        .await
+    }
+
+    /// Evaluates the checks of the group regardless of their cached outcomes,
+    /// which are replaced.
+    pub(crate) async fn get_fresh(&self, group: Option<&str>) -> HealthReport {
+        traced(group, async {
+            let (cfg, generation) = self.snapshot();
+            let report = self
+                .get_health_and_cache_if_success(&cfg, group, generation, HashMap::new())
+                .await;
+            self.gate(group, report)
+        })
+        .await
+    }
+
+    /// Sets whether the service accepts traffic, regardless of the checks.
+    pub(crate) fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::SeqCst);
+    }
+
+    /// Evaluates the liveness checks, or reports up without running any while
+    /// the service has been running for less than the startup grace period.
+    pub(crate) async fn get_liveness(&self, uptime: Duration) -> HealthReport {
+        if uptime < read(&self.cfg).startup_grace {
+            return HealthReport {
+                status: HealthStatus::Up,
+                checks: Arc::default(),
+                checked_at: self.clock.now(),
+            };
+        }
+        self.get(Some(HealthGroup::Liveness.name())).await
+    }
+
+    /// Reports readiness and overall health down while the service is gated off,
+    /// the other groups are left alone.
+    fn gate(&self, group: Option<&str>, mut report: HealthReport) -> HealthReport {
+        let gated = group.is_none_or(|group| group == HealthGroup::Readiness.name());
+        if gated && !self.ready.load(Ordering::SeqCst) {
+            report.status = HealthStatus::Down;
+        }
+        report
+    }
+
+    fn snapshot(&self) -> (HealthConfig<E>, u64) {
+        let cfg = read(&self.cfg);
+        (cfg.clone(), self.generation.load(Ordering::SeqCst))
+    }
+
+    /// Returns the key of each check, and whether it is mandatory, in the order
+    /// they were configured or registered.
+    pub(crate) fn keys(&self) -> Vec<(String, bool)> {
+        read(&self.cfg)
+            .checkers
+            .iter()
+            .map(|checker| (checker.key.clone(), checker.is_mandatory))
+            .collect()
+    }
+
+    /// Adds the check, replacing the one registered under the same key if any.
+    pub(crate) fn register(&self, checker: HealthChecker<E>) {
+        let mut cfg = write(&self.cfg);
+        cfg.checkers
+            .retain(|registered| registered.key != checker.key);
+        cfg.checkers.push(checker);
+        self.invalidate();
+    }
+
+    /// Removes the check registered under the key, reporting whether there was one.
+    pub(crate) fn deregister(&self, key: &str) -> bool {
+        let mut cfg = write(&self.cfg);
+        let len = cfg.checkers.len();
+        cfg.checkers.retain(|registered| registered.key != key);
+        let removed = cfg.checkers.len() != len;
+        if removed {
+            self.invalidate();
+        }
+        removed
+    }
+
+    fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        write(&self.results).clear();
+    }
+
+    /// Returns the cached outcomes of the checks of the group still within their
+    /// cache duration, and when each was checked.
+    fn get_from_cache(
+        &self,
+        cfg: &HealthConfig<E>,
+        group: Option<&str>,
+    ) -> HashMap<String, (HealthInfo, SystemTime)> {
+        let results = read(&self.results);
+        let now = self.clock.now();
+        cfg.checkers
+            .iter()
+            .filter(|checker| checker.in_group(group))
+            .filter_map(|checker| {
+                let (info, checked_at) = results.get(&checker.key)?;
+                let age = now.duration_since(*checked_at).unwrap_or(Duration::MAX);
+                let cache_duration = checker
+                    .cache_duration
+                    .or_else(|| {
+                        group.and_then(|group| cfg.group_cache_durations.get(group).copied())
+                    })
+                    .unwrap_or(cfg.cache_duration);
+                (age < cache_duration).then(|| (checker.key.clone(), (info.clone(), *checked_at)))
+            })
+            .collect()
+    }
+
+    /// Evaluates the checks not cached, caching the outcome of the successful
+    /// ones. A failed or skipped outcome evicts the cached one, including for a
+    /// cached check skipped because a dependency failed. The report is stamped
+    /// with the oldest cached outcome it merges, if any.
+    async fn get_health_and_cache_if_success(
+        &self,
+        cfg: &HealthConfig<E>,
+        group: Option<&str>,
+        generation: u64,
+        cached: HashMap<String, (HealthInfo, SystemTime)>,
+    ) -> HealthReport {
+        let oldest = cached.values().map(|(_, checked_at)| *checked_at).min();
+        let cached: HashMap<_, _> = cached
+            .into_iter()
+            .map(|(key, (info, _))| (key, info))
+            .collect();
+        let (checks, status) = evaluate(cfg, group, &cached).await;
+        let now = self.clock.now();
+        {
+            // Holding the checks lock orders the generation check before a
+            // concurrent registration.
+            let _cfg = read(&self.cfg);
+            if self.generation.load(Ordering::SeqCst) == generation {
+                let mut results = write(&self.results);
+                for (key, info) in checks
+                    .iter()
+                    .filter(|(key, info)| !info.success || !cached.contains_key(*key))
+                {
+                    if info.success {
+                        results.insert(key.clone(), (info.clone(), now));
+                    } else {
+                        results.remove(key);
+                    }
+                }
+            }
+        }
+        HealthReport {
+            status,
+            checks: Arc::new(checks),
+            checked_at: oldest.unwrap_or(now),
+        }
+    }
+}
+
+/// Runs the evaluation within a `health` span recording the group, the status
+/// and how long it took in milliseconds.
+#[cfg(feature = "tracing")]
+async fn traced(
+    group: Option<&str>,
+    evaluation: impl Future<Output = HealthReport>,
+) -> HealthReport {
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "health",
+        group = group.unwrap_or("all"),
+        status = tracing::field::Empty,
+        duration_ms = tracing::field::Empty,
+    );
+    let start = Instant::now();
+    let report = evaluation.instrument(span.clone()).await;
+    span.record("status", tracing::field::debug(report.status));
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    report
+}
+
+#[cfg(not(feature = "tracing"))]
+async fn traced(
+    _group: Option<&str>,
+    evaluation: impl Future<Output = HealthReport>,
+) -> HealthReport {
+    evaluation.await
+}
+
+/// Locks for reading, recovering the data from a holder that panicked: every
+/// write leaves it consistent, so a panic must not take the health checks down.
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks for writing, recovering the data from a holder that panicked like
+/// [`read`] does.
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs the checks of the group, every configured check without one, in the
+/// configured execution mode, each bounded by its own timeout or the configured
+/// one. The timeout does not cover the wait for a concurrency slot.
+///
+/// Checks run in waves, each after the ones it depends on. Dependencies outside
+/// of the evaluated checks are ignored, checks depending on a failed one or
+/// on each other are skipped.
+///
+/// The checks with a `cached` outcome are not run, the outcome is reported
+/// instead unless a dependency failed.
+///
+/// Returns the outcome of each check keyed by the checker key, and the aggregate
+/// status. Check errors are recorded through their `Display` implementation.
+/// Without any check the status is up, unless the config is strict.
+async fn evaluate<E: Error + Send + 'static>(
+    cfg: &HealthConfig<E>,
+    group: Option<&str>,
+    cached: &HashMap<String, HealthInfo>,
+) -> (HashMap<String, HealthInfo>, HealthStatus) {
+    let checkers: Vec<_> = cfg
+        .checkers
+        .iter()
+        .filter(|checker| checker.in_group(group))
+        .collect();
+    let mut status = if checkers.is_empty() && cfg.strict {
+        HealthStatus::Down
+    } else {
+        HealthStatus::Up
+    };
+    let evaluated: BTreeSet<_> = checkers
+        .iter()
+        .map(|checker| checker.key.as_str())
+        .collect();
+    let mut data = HashMap::with_capacity(checkers.len());
+    let mut pending = checkers;
+    while !pending.is_empty() {
+        let (ready, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|checker| {
+            checker
+                .depends_on
+                .iter()
+                .all(|dep| !evaluated.contains(dep.as_str()) || data.contains_key(dep))
+        });
+        let mut infos = Vec::with_capacity(ready.len());
+        let mut runnable = Vec::with_capacity(ready.len());
+        for checker in ready {
+            let failed = checker.depends_on.iter().find(|dep| {
+                data.get(*dep)
+                    .is_some_and(|info: &HealthInfo| !info.success)
+            });
+            match (failed, cached.get(&checker.key)) {
+                (Some(dep), _) => infos.push(HealthInfo::not_run(
+                    checker,
+                    format!("skipped, dependency {dep:?} failed"),
+                )),
+                (None, Some(info)) => infos.push(info.clone()),
+                (None, None) => runnable.push(checker),
+            }
+        }
+        let results = run(cfg, &runnable).await;
+        infos.extend(
+            runnable
+                .into_iter()
+                .zip(results)
+                .map(|(checker, (result, duration))| HealthInfo::new(checker, result, duration)),
+        );
+        if infos.is_empty() {
+            // The remaining checks depend on each other.
+            infos = waiting
+                .iter()
+                .map(|checker| {
+                    HealthInfo::not_run(checker, "skipped, dependency cycle".to_string())
+                })
+                .collect();
+            pending = Vec::new();
+        } else {
+            pending = waiting;
+        }
+        for info in infos {
+            if !info.success {
+                if info.is_mandatory {
+                    status = HealthStatus::Down;
+                } else if status == HealthStatus::Up
+                    && cfg.optional_failure_policy == OptionalFailurePolicy::Degrade
+                {
+                    status = HealthStatus::Degraded;
+                }
+            }
+            data.insert(info.key.clone(), info);
+        }
+    }
+    (data, status)
+}
+
+/// Runs the checks in the configured execution mode, returning the outcome of
+/// each in order.
+async fn run<E: Error + Send + 'static>(
+    cfg: &HealthConfig<E>,
+    checkers: &[&HealthChecker<E>],
+) -> Vec<(Result<HealthDetails, String>, Duration)> {
+    let timeout = |checker: &HealthChecker<E>| checker.timeout.unwrap_or(cfg.timeout);
+    match cfg.execution {
+        ExecutionMode::Concurrent => {
+            let semaphore = cfg.max_concurrency.map(Semaphore::new);
+            join_all(checkers.iter().map(|checker| async {
+                let _permit = match &semaphore {
+                    Some(semaphore) => Some(
+                        semaphore
+                            .acquire()
+                            .await
+                            .expect("semaphore is never closed"),
+                    ),
+                    None => None,
+                };
+                check(checker, timeout(checker)).await
+            }))
+            .await
+        }
+        ExecutionMode::Sequential => {
+            let mut results = Vec::with_capacity(checkers.len());
+            for checker in checkers {
+                results.push(check(checker, timeout(checker)).await);
+            }
+            results
+        }
+    }
+}
+
+/// Runs the check, retrying it on failure as configured, returning its last
+/// outcome and how long all the attempts took. A panicking attempt fails with
+/// the panic message.
+async fn check<E: Error>(
+    checker: &HealthChecker<E>,
+    timeout: Duration,
+) -> (Result<HealthDetails, String>, Duration) {
+    let start = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let attempt_result = AssertUnwindSafe(async { (checker.func)().await }).catch_unwind();
+        let result = match tokio::time::timeout(timeout, attempt_result).await {
+            Ok(Ok(result)) => result.map_err(|err| err.to_string()),
+            Ok(Err(panic)) => Err(panicked(panic)),
+            Err(_) => Err(format!("timed out after {timeout:?}")),
+        };
+        if result.is_ok() || attempt == checker.retries {
+            return (result, start.elapsed());
+        }
+        attempt += 1;
+        tokio::time::sleep(checker.retry_backoff).await;
+    }
+}
+
+/// Describes the panic of a check, with its message when it is a string.
+fn panicked(panic: Box<dyn Any + Send>) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str));
+    match message {
+        Some(message) => format!("check panicked: {message}"),
+        None => "check panicked".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize};
+
+    use super::*;
+
+    fn pass() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Ok(()) }.boxed()
+    }
+
+    fn fail() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Err(ActuatorError::new("connection refused")) }.boxed()
+    }
+
+    fn checker<E: 'static>(
+        key: &str,
+        is_mandatory: bool,
+        func: HealthCheckFn<E>,
+    ) -> HealthChecker<E> {
+        HealthChecker::new(key, health_check_fn(func))
+            .mandatory(is_mandatory)
+            .group(HealthGroup::Liveness)
+            .group(HealthGroup::Readiness)
+    }
+
+    #[tokio::test]
+    async fn test_memory_health_check() {
+        let check: HealthCheck<ActuatorError> = MemoryHealthCheck::new(0.0).into();
+        let err = check().await.err().unwrap();
+        assert!(err.to_string().starts_with("memory usage of "), "{err}");
+        assert!(err.to_string().ends_with("% exceeds the threshold of 0%"));
+
+        let check: HealthCheck<ActuatorError> = MemoryHealthCheck::new(100.0).into();
+        let details = check().await.unwrap();
+        #[cfg(feature = "serde")]
+        assert!(details.details.contains_key("memory_usage_percent"));
+        #[cfg(not(feature = "serde"))]
+        assert_eq!(details, HealthDetails::new());
+    }
+
+    /// Serves every request with the status until the test exits.
+    #[cfg(feature = "http-check")]
+    fn mock_server(status: u16) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} Mock\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+            }
+        });
+        format!("http://{addr}/health")
+    }
+
+    #[cfg(feature = "http-check")]
+    #[tokio::test]
+    async fn test_http_health_check() {
+        let url = mock_server(200);
+        let check: HealthCheck<ActuatorError> =
+            HttpHealthCheck::new(url.as_str(), 200, Duration::from_secs(5)).into();
+        let details = check().await.unwrap();
+        #[cfg(feature = "serde")]
+        assert_eq!(details.details["status"], 200);
+        #[cfg(not(feature = "serde"))]
+        assert_eq!(details, HealthDetails::new());
+
+        let url = mock_server(500);
+        let err = HttpHealthCheck::new(url.as_str(), 200, Duration::from_secs(5))
+            .check()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!("{url} answered with status 500, expected 200")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tcp_health_check() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let check: HealthCheck<ActuatorError> =
+            TcpHealthCheck::new(addr.to_string(), Duration::from_secs(1)).into();
+        let details = check().await.unwrap();
+        #[cfg(feature = "serde")]
+        assert_eq!(details.details["peer"], addr.to_string());
+        #[cfg(not(feature = "serde"))]
+        assert_eq!(details, HealthDetails::new());
+
+        drop(listener);
+        let err = TcpHealthCheck::new(addr.to_string(), Duration::from_secs(1))
+            .check()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .starts_with(&format!("cannot connect to {addr}: ")),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disk_space_health_check() {
+        let check: HealthCheck<ActuatorError> =
+            DiskSpaceHealthCheck::new(std::env::temp_dir(), 0).into();
+        let details = check().await.unwrap();
+        #[cfg(feature = "serde")]
+        assert!(details.details.contains_key("available_space"));
+        #[cfg(not(feature = "serde"))]
+        assert_eq!(details, HealthDetails::new());
+
+        let err = DiskSpaceHealthCheck::new(std::env::temp_dir(), u64::MAX)
+            .check()
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .ends_with(&format!("expected at least {}", u64::MAX)),
+            "{err}"
+        );
+
+        let err = DiskSpaceHealthCheck::new("/nonexistent", 0)
+            .check()
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("cannot resolve /nonexistent: "));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_all_passing() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass), checker("cache", false, pass)],
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Up);
+        assert_eq!(data.len(), 2);
+        assert!(data["db"].success);
+        assert_eq!(data["db"].error, None);
+        assert!(data["cache"].success);
+        assert_eq!(data["cache"].error, None);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_failing_mandatory() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, fail), checker("cache", false, pass)],
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(!data["db"].success);
+        assert_eq!(data["db"].error.as_deref(), Some("connection refused"));
+        assert!(data["cache"].success);
+        assert_eq!(data["cache"].error, None);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_failing_non_mandatory() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass), checker("cache", false, fail)],
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Degraded);
+        assert!(data["db"].success);
+        assert_eq!(data["db"].error, None);
+        assert!(!data["cache"].success);
+        assert!(!data["cache"].is_mandatory);
+        assert_eq!(data["cache"].error.as_deref(), Some("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_no_checkers() {
+        let (data, status) = evaluate(
+            &HealthConfig::<ActuatorError>::default(),
+            None,
+            &HashMap::new(),
+        )
+        .await;
+        assert_eq!(status, HealthStatus::Up);
+        assert!(data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_no_checkers_strict() {
+        let cfg = HealthConfig::<ActuatorError>::default().strict(true);
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(data.is_empty());
+
+        let cfg = cfg.checker(checker("db", true, pass));
+        assert_eq!(
+            evaluate(&cfg, None, &HashMap::new()).await.1,
+            HealthStatus::Up
+        );
+        assert_eq!(
+            evaluate(&cfg, Some(HealthGroup::Liveness.name()), &HashMap::new())
+                .await
+                .1,
+            HealthStatus::Up
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_serves_success_from_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cfg = HealthConfig::default()
+            .cache_duration(Duration::from_secs(60))
+            .checker(HealthChecker::new("db", counting(&calls, pass)));
+        let health = InnerHealth::new(cfg);
+        let first = health.get(None).await;
+        assert_eq!(first.status, HealthStatus::Up);
+        let second = health.get(None).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first.checked_at, second.checked_at);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_get_stamps_fresh_evaluation() {
+        let health = InnerHealth::new(HealthConfig::<ActuatorError>::default());
+        let before = SystemTime::now();
+        let first = health.get(None).await;
+        let second = health.get(None).await;
+        assert!(first.checked_at >= before);
+        assert!(second.checked_at >= first.checked_at);
+    }
+
+    #[tokio::test]
+    async fn test_get_does_not_cache_failure() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cfg = HealthConfig::default()
+            .cache_duration(Duration::from_secs(60))
+            .checker(HealthChecker::new("db", counting(&calls, fail)));
+        let health = InnerHealth::new(cfg);
+        assert_eq!(health.get(None).await.status, HealthStatus::Down);
+        assert_eq!(health.get(None).await.status, HealthStatus::Down);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_group_cache_duration() {
+        let (liveness_calls, external_calls) = (Arc::new(AtomicUsize::new(0)), Arc::default());
+        let cfg = HealthConfig::default()
+            .checker(
+                HealthChecker::new("disk", counting(&liveness_calls, pass))
+                    .group(HealthGroup::Liveness),
+            )
+            .checker(
+                HealthChecker::new("payments", counting(&external_calls, pass)).group("external"),
+            )
+            .cache_duration(Duration::from_secs(60))
+            .group_cache_duration(HealthGroup::Liveness, Duration::from_millis(50))
+            .group_cache_duration("external", Duration::from_secs(600));
+        let clock = fake_clock();
+        let health = InnerHealth::with_clock(cfg, clock.clone());
+        let calls = || {
+            (
+                liveness_calls.load(Ordering::SeqCst),
+                external_calls.load(Ordering::SeqCst),
+            )
+        };
+        let liveness = Some(HealthGroup::Liveness.name());
+        health.get(liveness).await;
+        health.get(Some("external")).await;
+        health.get(liveness).await;
+        health.get(Some("external")).await;
+        assert_eq!(calls(), (1, 1));
+
+        clock.advance(Duration::from_millis(80));
+        health.get(liveness).await;
+        health.get(Some("external")).await;
+        assert_eq!(calls(), (2, 1));
+    }
+
+    /// Check answering as `func` does, counting the calls.
+    fn counting(
+        calls: &Arc<AtomicUsize>,
+        func: HealthCheckFn<ActuatorError>,
+    ) -> HealthCheck<ActuatorError> {
+        let (calls, func) = (calls.clone(), health_check_fn(func));
+        Arc::new(move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            func()
+        })
+    }
+
+    /// Clock standing still until advanced.
+    struct FakeClock(std::sync::Mutex<SystemTime>);
+
+    impl FakeClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn fake_clock() -> Arc<FakeClock> {
+        Arc::new(FakeClock(std::sync::Mutex::new(SystemTime::UNIX_EPOCH)))
+    }
+
+    #[tokio::test]
+    async fn test_get_cache_expires_with_clock() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let clock = fake_clock();
+        let cfg = HealthConfig::default()
+            .cache_duration(Duration::from_secs(60))
+            .checker(HealthChecker::new("db", counting(&calls, pass)));
+        let health = InnerHealth::with_clock(cfg, clock.clone());
+        let first = health.get(None).await;
+        assert_eq!(first.checked_at, SystemTime::UNIX_EPOCH);
+
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(health.get(None).await, first);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        clock.advance(Duration::from_secs(1));
+        let report = health.get(None).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            report.checked_at,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_checker_cache_duration() {
+        let (cheap_calls, expensive_calls) = (Arc::new(AtomicUsize::new(0)), Arc::default());
+        let cfg = HealthConfig::default()
+            .cache_duration(Duration::from_secs(60))
+            .checker(
+                HealthChecker::new("cheap", counting(&cheap_calls, pass))
+                    .cache_duration(Duration::from_millis(50)),
+            )
+            .checker(
+                HealthChecker::new("expensive", counting(&expensive_calls, pass))
+                    .cache_duration(Duration::from_secs(600)),
+            );
+        let clock = fake_clock();
+        let health = InnerHealth::with_clock(cfg, clock.clone());
+        let calls = || {
+            (
+                cheap_calls.load(Ordering::SeqCst),
+                expensive_calls.load(Ordering::SeqCst),
+            )
+        };
+        let first = health.get(None).await;
+        health.get(None).await;
+        assert_eq!(calls(), (1, 1));
+
+        clock.advance(Duration::from_millis(80));
+        let report = health.get(None).await;
+        assert_eq!(calls(), (2, 1));
+        assert_eq!(report.status, HealthStatus::Up);
+        assert!(report.checks["cheap"].success);
+        assert_eq!(report.checks["expensive"], first.checks["expensive"]);
+        // Stamped with the oldest outcome merged.
+        assert_eq!(report.checked_at, first.checked_at);
+
+        health.get_fresh(None).await;
+        assert_eq!(calls(), (3, 2));
+    }
+
+    #[tokio::test]
+    async fn test_get_cached_dependent_of_failed_check() {
+        let connected = Arc::new(AtomicBool::new(true));
+        let state = connected.clone();
+        let connection: HealthCheck<ActuatorError> = Arc::new(move || {
+            let connected = state.load(Ordering::SeqCst);
+            async move {
+                if connected {
+                    Ok(HealthDetails::new())
+                } else {
+                    Err(ActuatorError::new("connection refused"))
+                }
+            }
+            .boxed()
+        });
+        let query_calls = Arc::new(AtomicUsize::new(0));
+        let cfg = HealthConfig::default()
+            .checker(HealthChecker::new("connection", connection).cache_duration(Duration::ZERO))
+            .checker(
+                HealthChecker::new("query", counting(&query_calls, pass))
+                    .depends_on("connection")
+                    .cache_duration(Duration::from_secs(600)),
+            );
+        let health = InnerHealth::with_clock(cfg, fake_clock());
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+
+        connected.store(false, Ordering::SeqCst);
+        let report = health.get(None).await;
+        assert_eq!(report.status, HealthStatus::Down);
+        assert!(!report.checks["query"].success);
+        assert!(report.checks["query"].skipped);
+
+        // The skipped outcome replaced the cached one.
+        connected.store(true, Ordering::SeqCst);
+        let report = health.get(None).await;
+        assert_eq!(report.status, HealthStatus::Up);
+        assert!(!report.checks["query"].skipped);
+        assert_eq!(query_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_fresh_bypasses_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cfg = HealthConfig::default()
+            .cache_duration(Duration::from_secs(60))
+            .checker(HealthChecker::new("db", counting(&calls, pass)));
+        let health = InnerHealth::new(cfg);
+        let cached = health.get(None).await;
+        let fresh = health.get_fresh(None).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(fresh.checked_at >= cached.checked_at);
+        assert_eq!(health.get(None).await.checked_at, fresh.checked_at);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_fresh_failure_clears_cache() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass)],
+            cache_duration: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let health = InnerHealth::new(cfg);
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+        health.cfg.write().unwrap().checkers = vec![checker("db", true, fail)];
+        assert_eq!(health.get_fresh(None).await.status, HealthStatus::Down);
+        assert!(health.results.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_poisoned_locks() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass)],
+            cache_duration: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let health = InnerHealth::new(cfg);
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _cfg = health.cfg.write().unwrap();
+                    let _results = health.results.write().unwrap();
+                    panic!("checker panicked");
+                })
+                .join()
+                .unwrap_err();
+        });
+        assert!(health.cfg.is_poisoned());
+        assert!(health.results.is_poisoned());
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+        let report = health.get_fresh(Some(HealthGroup::Liveness.name())).await;
+        assert!(report.checks["db"].success);
+        health.register(checker("cache", false, fail));
+        assert_eq!(health.get(None).await.status, HealthStatus::Degraded);
+        assert!(health.deregister("cache"));
+    }
+
+    /// Records the spans and events as `name field=value` lines.
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Capture(Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[cfg(feature = "tracing")]
+    impl Capture {
+        fn lines(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    struct Fields(String);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for Fields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Capture {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = Fields(format!("span {}", attrs.metadata().name()));
+            attrs.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+
+        fn on_record(
+            &self,
+            _: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = Fields("record".to_string());
+            values.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = Fields("event".to_string());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_get_traced() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = Capture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass)],
+            cache_duration: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let health = InnerHealth::new(cfg);
+        health.get(None).await;
+        health.get(Some(HealthGroup::Liveness.name())).await;
+        health.get(None).await;
+        let lines: Vec<_> = capture
+            .lines()
+            .into_iter()
+            .map(|line| match line.split_once(" duration_ms=") {
+                Some((line, millis)) => {
+                    assert!(millis.parse::<u64>().is_ok(), "{millis}");
+                    format!("{line} duration_ms")
+                }
+                None => line,
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "span health group=\"all\"",
+                "event message=health cache miss",
+                "record status=Up",
+                "record duration_ms",
+                "span health group=\"liveness\"",
+                "event message=health cache hit",
+                "record status=Up",
+                "record duration_ms",
+                "span health group=\"all\"",
+                "event message=health cache hit",
+                "record status=Up",
+                "record duration_ms",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_gated_off() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass)],
+            cache_duration: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let health = InnerHealth::new(cfg);
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+        health.set_ready(false);
+        let report = health.get(Some(HealthGroup::Readiness.name())).await;
+        assert_eq!(report.status, HealthStatus::Down);
+        assert!(report.checks["db"].success);
+        assert_eq!(health.get(None).await.status, HealthStatus::Down);
+        assert_eq!(health.get_fresh(None).await.status, HealthStatus::Down);
+        assert_eq!(
+            health.get(Some(HealthGroup::Liveness.name())).await.status,
+            HealthStatus::Up
+        );
+        health.set_ready(true);
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+        assert_eq!(
+            health.get(Some(HealthGroup::Readiness.name())).await.status,
+            HealthStatus::Up
+        );
+    }
+
+    /// Check failing on its first call only, counting the calls.
+    fn flaky(calls: &Arc<AtomicUsize>) -> HealthCheck<ActuatorError> {
+        let calls = calls.clone();
+        Arc::new(move || {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match call {
+                    0 => Err(ActuatorError::new("connection reset")),
+                    _ => Ok(HealthDetails::new()),
+                }
+            }
+            .boxed()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_retries() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut db = checker("db", true, pass);
+        db.func = flaky(&calls);
+        db.retries = 1;
+        db.retry_backoff = Duration::from_millis(20);
+        let (data, status) =
+            evaluate(&HealthConfig::default().checker(db), None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Up);
+        assert!(data["db"].success);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(data["db"].duration >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_retries_exhausted() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let db = HealthChecker::new("db", counting(&calls, fail)).retries(2, Duration::ZERO);
+        let (data, status) =
+            evaluate(&HealthConfig::default().checker(db), None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert_eq!(data["db"].error.as_deref(), Some("connection refused"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    fn hang() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_timed_out_mandatory() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, hang), checker("cache", false, pass)],
+            timeout: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(!data["db"].success);
+        assert_eq!(data["db"].error.as_deref(), Some("timed out after 20ms"));
+        assert!(data["cache"].success);
+    }
+
+    fn boom() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { panic!("pool exhausted") }.boxed()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_panicked() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, boom), checker("cache", false, pass)],
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(!data["db"].success);
+        assert_eq!(
+            data["db"].error.as_deref(),
+            Some("check panicked: pool exhausted")
+        );
+        assert!(data["cache"].success);
+    }
+
+    fn slow() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_records_duration() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, slow)],
+            ..Default::default()
+        };
+        let (data, _) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert!(data["db"].duration >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_checker_timeout_override() {
+        let mut batch = checker("batch", true, slow);
+        batch.timeout = Some(Duration::from_secs(5));
+        let cfg = HealthConfig {
+            checkers: vec![batch, checker("db", true, slow)],
+            timeout: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(data["batch"].success);
+        assert!(!data["db"].success);
+        assert_eq!(data["db"].error.as_deref(), Some("timed out after 10ms"));
+    }
+
+    /// Checks tracking how many of them run at once, and the most seen.
+    fn tracked(running: &Arc<AtomicUsize>, max: &Arc<AtomicUsize>) -> Vec<HealthChecker> {
+        ["db", "cache", "search"]
+            .into_iter()
+            .map(|key| {
+                let (running, max) = (running.clone(), max.clone());
+                let func: HealthCheck<ActuatorError> = Arc::new(move || {
+                    let (running, max) = (running.clone(), max.clone());
+                    async move {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(HealthDetails::new())
+                    }
+                    .boxed()
+                });
+                HealthChecker::new(key, func)
+                    .group(HealthGroup::Liveness)
+                    .group(HealthGroup::Readiness)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_sequential() {
+        let (running, max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let cfg = HealthConfig {
+            checkers: tracked(&running, &max),
+            execution: ExecutionMode::Sequential,
+            ..Default::default()
+        };
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Up);
+        assert_eq!(data.len(), 3);
+        assert_eq!(max.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_concurrent() {
+        let (running, max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let cfg = HealthConfig {
+            checkers: tracked(&running, &max),
+            ..Default::default()
+        };
+        assert_eq!(
+            evaluate(&cfg, None, &HashMap::new()).await.1,
+            HealthStatus::Up
+        );
+        assert_eq!(max.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_max_concurrency() {
+        let (running, max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let cfg = HealthConfig::default().max_concurrency(2);
+        let cfg = tracked(&running, &max)
+            .into_iter()
+            .fold(cfg, HealthConfig::checker);
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Up);
+        assert_eq!(data.len(), 3);
+        assert_eq!(max.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_serialize() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass), checker("cache", false, fail)],
+            ..Default::default()
+        };
+        let (data, _) = evaluate(&cfg, None, &HashMap::new()).await;
+        let json = serde_json::to_value(&data).unwrap();
+        let mut keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["cache", "db"]);
+        assert_eq!(json["db"]["success"], true);
+        assert!(json["db"].get("error").is_none());
+        assert_eq!(json["cache"]["success"], false);
+        assert_eq!(json["cache"]["is_mandatory"], false);
+        assert_eq!(json["cache"]["error"], "connection refused");
+        assert!(json["db"]["duration_ms"].is_u64());
+        assert!(json["db"].get("details").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    fn pool() -> BoxFuture<'static, Result<HealthDetails, ActuatorError>> {
+        async { Ok(HealthDetails::new().with("pool_active", 3)) }.boxed()
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_serialize_details() {
+        let cfg = HealthConfig::default()
+            .checker(HealthChecker::new("db", health_check_fn(pool)).group(HealthGroup::Readiness))
+            .checker(checker("cache", false, fail));
+        let (data, _) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(data["db"].details["pool_active"], 3);
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["db"]["details"], serde_json::json!({"pool_active": 3}));
+        assert!(json["cache"].get("details").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_report() {
+        let report = HealthReport {
+            status: HealthStatus::Degraded,
+            checks: Arc::new(HashMap::new()),
+            checked_at: SystemTime::UNIX_EPOCH,
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "DEGRADED");
+        assert!(json["checks"].as_object().unwrap().is_empty());
+        assert_eq!(json["checked_at"], "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_checker_builder() {
+        let checker = HealthChecker::<ActuatorError>::new("db", health_check_fn(pass));
+        assert_eq!(checker.key, "db");
+        assert!(checker.is_mandatory);
+        assert!(checker.groups.is_empty());
+        assert_eq!(checker.timeout, None);
+        assert_eq!(checker.retries, 0);
+        assert!(checker.depends_on.is_empty());
+
+        let checker = checker
+            .mandatory(false)
+            .timeout(Duration::from_secs(1))
+            .group(HealthGroup::Liveness)
+            .group("external")
+            .retries(2, Duration::from_millis(10))
+            .depends_on("connection");
+        assert!(!checker.is_mandatory);
+        assert_eq!(checker.timeout, Some(Duration::from_secs(1)));
+        assert_eq!(
+            checker.groups,
+            BTreeSet::from(["external".to_string(), "liveness".to_string()])
+        );
+        assert_eq!(checker.retries, 2);
+        assert_eq!(checker.retry_backoff, Duration::from_millis(10));
+        assert_eq!(checker.depends_on, ["connection"]);
+    }
+
+    #[tokio::test]
+    async fn test_info_getters() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass), checker("cache", false, fail)],
+            ..Default::default()
+        };
+        let (data, _) = evaluate(&cfg, None, &HashMap::new()).await;
+        let db = &data["db"];
+        assert_eq!(db.key(), "db");
+        assert!(db.is_mandatory());
+        assert!(db.success());
+        assert!(!db.skipped());
+        assert_eq!(db.error(), None);
+        let cache = &data["cache"];
+        assert!(!cache.is_mandatory());
+        assert!(!cache.success());
+        assert!(cache.error().is_some());
+        assert!(cache.duration() < cfg.timeout);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_duration_millis() {
+        let info = HealthInfo {
+            key: "db".to_string(),
+            is_mandatory: true,
+            success: true,
+            error: None,
+            duration: Duration::from_micros(12_700),
+            details: HashMap::new(),
+            skipped: false,
+        };
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["duration_ms"], 12);
+        assert!(json.get("duration").is_none());
+        assert!(json.get("skipped").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_optional_failure_policy() {
+        for (policy, expected) in [
+            (OptionalFailurePolicy::Degrade, HealthStatus::Degraded),
+            (OptionalFailurePolicy::Ignore, HealthStatus::Up),
+        ] {
+            let cfg = HealthConfig::default()
+                .optional_failure_policy(policy)
+                .checker(checker("db", true, pass))
+                .checker(checker("cache", false, fail));
+            let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+            assert_eq!(status, expected, "{policy:?}");
+            assert!(!data["cache"].success);
+
+            let cfg = cfg.checker(checker("disk", true, fail));
+            assert_eq!(
+                evaluate(&cfg, None, &HashMap::new()).await.1,
+                HealthStatus::Down,
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_group() {
+        let mut db = checker("db", true, fail);
+        db.groups = [HealthGroup::Readiness].map(String::from).into();
+        let mut disk = checker("disk", true, pass);
+        disk.groups = [HealthGroup::Liveness].map(String::from).into();
+        let cfg = HealthConfig {
+            checkers: vec![db, disk, checker("cache", false, pass)],
+            ..Default::default()
+        };
+
+        let (data, status) =
+            evaluate(&cfg, Some(HealthGroup::Liveness.name()), &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Up);
+        let mut keys: Vec<_> = data.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["cache", "disk"]);
+
+        let (data, status) =
+            evaluate(&cfg, Some(HealthGroup::Readiness.name()), &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        let mut keys: Vec<_> = data.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["cache", "db"]);
+
+        assert_eq!(evaluate(&cfg, None, &HashMap::new()).await.0.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_dependencies() {
+        let query_calls = Arc::new(AtomicUsize::new(0));
+        let query = HealthChecker::new("query", counting(&query_calls, pass))
+            .mandatory(false)
+            .depends_on("connection");
+        let mut cache = checker("cache", false, pass);
+        cache.depends_on = vec!["external".into()];
+        let cfg = HealthConfig {
+            checkers: vec![query, checker("connection", true, fail), cache],
+            ..Default::default()
+        };
+
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert_eq!(query_calls.load(Ordering::SeqCst), 0);
+        let query = &data["query"];
+        assert!(!query.success);
+        assert!(query.skipped);
+        assert_eq!(
+            query.error.as_deref(),
+            Some("skipped, dependency \"connection\" failed")
+        );
+        assert!(!data["connection"].skipped);
+        assert!(data["cache"].success);
+        #[cfg(feature = "serde")]
+        assert_eq!(serde_json::to_value(query).unwrap()["skipped"], true);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_dependency_cycle() {
+        let mut db = checker("db", false, pass);
+        db.depends_on = vec!["cache".into()];
+        let mut cache = checker("cache", false, pass);
+        cache.depends_on = vec!["db".into()];
+        let cfg = HealthConfig {
+            checkers: vec![db, cache, checker("disk", true, pass)],
+            ..Default::default()
+        };
+
+        let (data, status) = evaluate(&cfg, None, &HashMap::new()).await;
+        assert_eq!(status, HealthStatus::Degraded);
+        assert!(data["disk"].success);
+        for key in ["db", "cache"] {
+            assert!(data[key].skipped);
+            assert_eq!(
+                data[key].error.as_deref(),
+                Some("skipped, dependency cycle")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_invalidates_cache() {
+        let cfg = HealthConfig {
+            checkers: vec![checker("db", true, pass)],
+            cache_duration: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let health = InnerHealth::new(cfg);
+        assert_eq!(health.get(None).await.checks.len(), 1);
+
+        health.register(checker("cache", false, fail));
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = health.get(None).await;
+        assert_eq!(status, HealthStatus::Degraded);
+        assert!(!data["cache"].success);
+
+        health.register(checker("cache", false, pass));
+        let HealthReport { checks: data, .. } = health.get(None).await;
+        assert_eq!(data.len(), 2);
+        assert!(data["cache"].success);
+
+        assert!(health.deregister("cache"));
+        assert!(!health.deregister("cache"));
+        let HealthReport { checks: data, .. } = health.get(None).await;
+        assert_eq!(data.len(), 1);
+        assert!(!data.contains_key("cache"));
+    }
+
+    #[tokio::test]
+    async fn test_register_stateful_closure() {
+        let connected = Arc::new(AtomicBool::new(true));
+        let state = connected.clone();
+        let health = InnerHealth::new(HealthConfig::default());
+        let func: HealthCheck<ActuatorError> = Arc::new(move || {
+            let connected = state.load(Ordering::SeqCst);
+            async move {
+                if connected {
+                    Ok(HealthDetails::new())
+                } else {
+                    Err(ActuatorError::new("disconnected"))
+                }
+            }
+            .boxed()
+        });
+        health.register(HealthChecker::new("db", func).group(HealthGroup::Readiness));
+        assert_eq!(health.get(None).await.status, HealthStatus::Up);
+
+        connected.store(false, Ordering::SeqCst);
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = health.get(None).await;
+        assert_eq!(status, HealthStatus::Down);
+        assert_eq!(data["db"].error.as_deref(), Some("disconnected"));
+    }
+}
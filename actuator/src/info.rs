@@ -0,0 +1,529 @@
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::env;
+#[cfg(feature = "serde")]
+use crate::error::ActuatorError;
+
+/// Keys of the built-in info sections, contributors cannot use them.
+#[cfg(feature = "serde")]
+const RESERVED_KEYS: [&str; 4] = ["application", "git", "runtime", "extra"];
+
+/// Static information about the running service.
+///
+/// `extra` holds the `ACTUATOR_INFO_*` environment variables read at startup,
+/// keyed by the rest of their name in lowercase, so `ACTUATOR_INFO_BUILD_NUMBER`
+/// surfaces as `build_number`. When serialized, the blocks added by
+/// contributors follow the built-in sections under their keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Info {
+    pub(crate) application: ApplicationInfo,
+    pub(crate) git: GITInfo,
+    pub(crate) runtime: RuntimeInfo,
+    pub(crate) extra: HashMap<String, String>,
+    #[cfg(feature = "serde")]
+    #[serde(flatten)]
+    pub(crate) contributed: BTreeMap<String, serde_json::Value>,
+}
+
+/// Details of the application as configured.
+///
+/// When serialized, `startup_stamp` is rendered as RFC 3339 and followed by
+/// the `uptime_seconds` elapsed since.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationInfo {
+    pub(crate) name: String,
+    pub(crate) env: String,
+    pub(crate) version: String,
+    pub(crate) startup_stamp: SystemTime,
+}
+
+impl ApplicationInfo {
+    /// Name of the application.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Environment the application runs in.
+    pub fn env(&self) -> &str {
+        &self.env
+    }
+
+    /// Version of the application.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// When the application started.
+    pub fn startup_stamp(&self) -> SystemTime {
+        self.startup_stamp
+    }
+
+    /// Time elapsed since startup, zero if the clock moved back since.
+    pub(crate) fn uptime(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.startup_stamp)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ApplicationInfo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ApplicationInfo", 5)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("env", &self.env)?;
+        state.serialize_field("version", &self.version)?;
+        state.serialize_field(
+            "startup_stamp",
+            &humantime::format_rfc3339(self.startup_stamp).to_string(),
+        )?;
+        state.serialize_field("uptime_seconds", &self.uptime().as_secs())?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for ApplicationInfo {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ApplicationInfo".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let text = generator.subschema_for::<String>();
+        crate::schema::object([
+            ("name", text.clone()),
+            ("env", text.clone()),
+            ("version", text),
+            ("startup_stamp", crate::schema::date_time(generator)),
+            ("uptime_seconds", crate::schema::whole_units(generator)),
+        ])
+    }
+}
+
+/// Details of the commit the application was built from, and whether the
+/// working tree had uncommitted changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GITInfo {
+    pub(crate) commit_id: String,
+    pub(crate) commit_timestamp: String,
+    pub(crate) branch: String,
+    pub(crate) describe: String,
+    pub(crate) dirty: bool,
+}
+
+impl GITInfo {
+    /// Hash of the commit.
+    pub fn commit_id(&self) -> &str {
+        &self.commit_id
+    }
+
+    /// When the commit was made.
+    pub fn commit_timestamp(&self) -> &str {
+        &self.commit_timestamp
+    }
+
+    /// Branch the commit was checked out from.
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    /// Output of `git describe` for the commit.
+    pub fn describe(&self) -> &str {
+        &self.describe
+    }
+
+    /// Whether the working tree had uncommitted changes.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+/// Details of the toolchain, the build profile and the platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RuntimeInfo {
+    pub(crate) os: String,
+    pub(crate) arch: String,
+    pub(crate) rust_version: String,
+    pub(crate) rust_channel: String,
+    pub(crate) cargo_profile: String,
+    pub(crate) opt_level: String,
+    pub(crate) target_triple: String,
+    pub(crate) debug_assertions: bool,
+    pub(crate) actuator_version: String,
+    pub(crate) package_version: String,
+}
+
+impl RuntimeInfo {
+    /// Operating system, as in [`std::env::consts::OS`].
+    pub fn os(&self) -> &str {
+        &self.os
+    }
+
+    /// CPU architecture, as in [`std::env::consts::ARCH`].
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    /// Version of the compiler.
+    pub fn rust_version(&self) -> &str {
+        &self.rust_version
+    }
+
+    /// Release channel of the compiler, such as `stable`.
+    pub fn rust_channel(&self) -> &str {
+        &self.rust_channel
+    }
+
+    /// Cargo profile of the build, such as `release`.
+    pub fn cargo_profile(&self) -> &str {
+        &self.cargo_profile
+    }
+
+    /// Optimization level of the build.
+    pub fn opt_level(&self) -> &str {
+        &self.opt_level
+    }
+
+    /// Target triple the application was built for.
+    pub fn target_triple(&self) -> &str {
+        &self.target_triple
+    }
+
+    /// Whether debug assertions are enabled.
+    pub fn debug_assertions(&self) -> bool {
+        self.debug_assertions
+    }
+
+    /// Version of this crate.
+    pub fn actuator_version(&self) -> &str {
+        &self.actuator_version
+    }
+
+    /// Cargo package version of the application as configured, empty unless
+    /// set through [`ConfigBuilder::package_version`](crate::ConfigBuilder::package_version).
+    pub fn package_version(&self) -> &str {
+        &self.package_version
+    }
+}
+
+impl Info {
+    /// Details of the application as configured.
+    pub fn application(&self) -> &ApplicationInfo {
+        &self.application
+    }
+
+    /// Details of the commit the application was built from.
+    pub fn git(&self) -> &GITInfo {
+        &self.git
+    }
+
+    /// Details of the toolchain, the build profile and the platform.
+    pub fn runtime(&self) -> &RuntimeInfo {
+        &self.runtime
+    }
+
+    /// The `ACTUATOR_INFO_*` environment variables read at startup.
+    pub fn extra(&self) -> &HashMap<String, String> {
+        &self.extra
+    }
+
+    /// Blocks added by contributors, keyed by their key.
+    #[cfg(feature = "serde")]
+    pub fn contributed(&self) -> &BTreeMap<String, serde_json::Value> {
+        &self.contributed
+    }
+
+    pub(crate) fn new<E>(cfg: &Config<E>) -> Self {
+        Self {
+            application: ApplicationInfo {
+                name: cfg.name.clone(),
+                env: cfg.env.clone(),
+                version: cfg.version.clone(),
+                startup_stamp: SystemTime::now(),
+            },
+            git: GITInfo {
+                commit_id: env::git_sha(),
+                commit_timestamp: env::git_commit_timestamp(),
+                branch: env::git_branch(),
+                describe: env::git_describe(),
+                dirty: env::git_dirty(),
+            },
+            runtime: RuntimeInfo {
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+                rust_version: env::rustc_semver(),
+                rust_channel: env::rustc_channel(),
+                cargo_profile: env::cargo_profile(),
+                opt_level: env::cargo_opt_level(),
+                target_triple: env::cargo_target_triple(),
+                debug_assertions: cfg!(debug_assertions),
+                actuator_version: env!("CARGO_PKG_VERSION").to_string(),
+                package_version: cfg.package_version.clone(),
+            },
+            extra: env::info_extra(),
+            #[cfg(feature = "serde")]
+            contributed: BTreeMap::new(),
+        }
+    }
+
+    /// Maps the application and runtime details to OpenTelemetry resource
+    /// attributes following the semantic conventions, leaving out empty values.
+    pub(crate) fn otel_resource_attributes(&self) -> Vec<(String, String)> {
+        let os = match self.runtime.os.as_str() {
+            "macos" | "ios" => "darwin",
+            os => os,
+        };
+        let arch = match self.runtime.arch.as_str() {
+            "x86_64" => "amd64",
+            "aarch64" => "arm64",
+            "arm" => "arm32",
+            arch => arch,
+        };
+        [
+            ("service.name", self.application.name.as_str()),
+            ("service.version", &self.application.version),
+            ("deployment.environment", &self.application.env),
+            ("os.type", os),
+            ("host.arch", arch),
+            ("process.runtime.name", "rustc"),
+            ("process.runtime.version", &self.runtime.rust_version),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    /// Weak ETag of the serialized info, leaving out the uptime so that it only
+    /// changes along the content, e.g. when a block is contributed.
+    #[cfg(feature = "serde")]
+    pub(crate) fn etag(&self) -> String {
+        let mut json = serde_json::to_value(self).unwrap_or_default();
+        if let Some(application) = json["application"].as_object_mut() {
+            application.remove("uptime_seconds");
+        }
+        // FNV-1a, stable across builds unlike the hasher of the standard library.
+        let hash = json
+            .to_string()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("W/\"{hash:016x}\"")
+    }
+
+    /// Adds a block under the key, replacing the one contributed under the same
+    /// key if any. Keys of the built-in sections are rejected.
+    #[cfg(feature = "serde")]
+    pub(crate) fn contribute(
+        &mut self,
+        key: String,
+        value: serde_json::Value,
+    ) -> Result<(), ActuatorError> {
+        if RESERVED_KEYS.contains(&key.as_str()) {
+            return Err(ActuatorError::new(format!("info key {key:?} is reserved")));
+        }
+        self.contributed.insert(key, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> Info {
+        let cfg = Config::builder()
+            .name("orders")
+            .env("test")
+            .version("1.0.0")
+            .package_version("1.0.0-rc.1")
+            .port(8080)
+            .build()
+            .unwrap();
+        Info::new(&cfg)
+    }
+
+    #[test]
+    fn test_uptime() {
+        let info = info();
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(info.application.uptime() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_getters() {
+        let mut info = info();
+        info.git.commit_id = "8f3c2a1".to_string();
+        info.git.dirty = true;
+        assert_eq!(info.application().name(), "orders");
+        assert_eq!(info.application().env(), "test");
+        assert_eq!(info.application().version(), "1.0.0");
+        assert!(info.application().startup_stamp() <= SystemTime::now());
+        assert_eq!(info.git().commit_id(), "8f3c2a1");
+        assert!(info.git().dirty());
+        assert_eq!(info.runtime().os(), std::env::consts::OS);
+        assert_eq!(info.runtime().arch(), std::env::consts::ARCH);
+        assert_eq!(info.runtime().debug_assertions(), cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn test_versions() {
+        let info = info();
+        assert!(!info.runtime().actuator_version().is_empty());
+        assert_eq!(info.runtime().actuator_version(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.runtime().package_version(), "1.0.0-rc.1");
+    }
+
+    #[test]
+    fn test_debug_assertions() {
+        assert_eq!(info().runtime.debug_assertions, cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn test_uptime_clock_moved_back() {
+        let mut info = info();
+        info.application.startup_stamp = SystemTime::now() + Duration::from_secs(3600);
+        assert_eq!(info.application.uptime(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_otel_resource_attributes() {
+        let mut info = info();
+        info.runtime.os = "macos".to_string();
+        info.runtime.arch = "x86_64".to_string();
+        info.runtime.rust_version = "1.85.0".to_string();
+        assert_eq!(
+            info.otel_resource_attributes(),
+            [
+                ("service.name", "orders"),
+                ("service.version", "1.0.0"),
+                ("deployment.environment", "test"),
+                ("os.type", "darwin"),
+                ("host.arch", "amd64"),
+                ("process.runtime.name", "rustc"),
+                ("process.runtime.version", "1.85.0"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_otel_resource_attributes_skips_empty() {
+        let mut info = info();
+        info.application.version = String::new();
+        info.runtime.rust_version = String::new();
+        let keys: Vec<_> = info
+            .otel_resource_attributes()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert!(!keys.contains(&"service.version".to_string()));
+        assert!(!keys.contains(&"process.runtime.version".to_string()));
+        assert!(keys.contains(&"service.name".to_string()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let mut info = info();
+        info.application.startup_stamp = SystemTime::UNIX_EPOCH;
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["application"]["name"], "orders");
+        assert_eq!(json["application"]["env"], "test");
+        assert_eq!(json["application"]["version"], "1.0.0");
+        assert_eq!(json["application"]["startup_stamp"], "1970-01-01T00:00:00Z");
+        assert!(json["application"]["uptime_seconds"].as_u64().unwrap() > 0);
+        for key in ["commit_id", "commit_timestamp", "branch", "describe"] {
+            assert!(json["git"][key].is_string(), "missing git.{key}");
+        }
+        assert!(json["git"]["dirty"].is_boolean());
+        for key in [
+            "os",
+            "arch",
+            "rust_version",
+            "rust_channel",
+            "cargo_profile",
+            "opt_level",
+            "target_triple",
+            "actuator_version",
+        ] {
+            assert!(json["runtime"][key].is_string(), "missing runtime.{key}");
+        }
+        assert!(json["runtime"]["debug_assertions"].is_boolean());
+        assert_eq!(json["runtime"]["package_version"], "1.0.0-rc.1");
+        assert!(json["extra"].is_object());
+    }
+
+    #[test]
+    fn test_extra() {
+        let _lock = env::ENV_LOCK.lock().unwrap();
+        env::set_var("ACTUATOR_INFO_BUILD_NUMBER", "42");
+        let info = info();
+        env::remove_var("ACTUATOR_INFO_BUILD_NUMBER");
+        assert_eq!(info.extra["build_number"], "42");
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_value(&info).unwrap()["extra"]["build_number"],
+            "42"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_etag() {
+        let mut info = info();
+        let etag = info.etag();
+        assert!(etag.starts_with("W/\"") && etag.ends_with('"'), "{etag}");
+        assert_eq!(etag.len(), 20);
+        info.application.startup_stamp -= Duration::from_secs(60);
+        let moved = info.etag();
+        assert_ne!(moved, etag);
+        assert_eq!(info.clone().etag(), moved);
+        info.contribute("region".to_string(), serde_json::json!("eu-west-1"))
+            .unwrap();
+        assert_ne!(info.etag(), moved);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_contribute() {
+        let mut info = info();
+        info.contribute("region".to_string(), serde_json::json!("eu-west-1"))
+            .unwrap();
+        info.contribute("features".to_string(), serde_json::json!({"beta": true}))
+            .unwrap();
+        info.contribute("region".to_string(), serde_json::json!("us-east-1"))
+            .unwrap();
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["region"], "us-east-1");
+        assert_eq!(json["features"]["beta"], true);
+        assert_eq!(json["application"]["name"], "orders");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_contribute_reserved_key() {
+        let mut info = info();
+        for key in ["application", "git", "runtime", "extra"] {
+            let err = info
+                .contribute(key.to_string(), serde_json::json!({}))
+                .err()
+                .unwrap();
+            assert_eq!(err.to_string(), format!("info key \"{key}\" is reserved"));
+        }
+        assert!(info.contributed.is_empty());
+    }
+}
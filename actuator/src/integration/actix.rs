@@ -0,0 +1,568 @@
+use std::error::Error;
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServerHandle, ServiceRequest, ServiceResponse};
+use actix_web::http::{StatusCode, header};
+use actix_web::middleware::{self, Next};
+use actix_web::rt::System;
+use actix_web::web::{self, Data};
+use actix_web::{HttpRequest, HttpResponse, Scope};
+use serde::Serialize;
+
+use crate::integration::{SHUTDOWN_TOKEN_HEADER, etag_matches, json_body, shutdown_token};
+use crate::{Actuator, Endpoint, ShutdownStrategy};
+
+impl<E: Error + Send + 'static> Actuator<E> {
+    /// Builds an [`actix_web::Scope`] mounted under the configured base path
+    /// serving the enabled endpoints as JSON, requests to the disabled ones are
+    /// answered with 404.
+    ///
+    /// The ping endpoint answers with the configured payload as is. The health
+    /// endpoint answers with the [`Actuator::health_status_code`] of the status.
+    /// Requests to the endpoints requiring basic authentication are answered
+    /// with 401 unless they carry the credentials. The base path itself serves
+    /// the [`Actuator::index`]. The info endpoint answers with 304 when
+    /// `If-None-Match` carries the [`Actuator::info_etag`]. The shutdown
+    /// endpoint answers with 403 unless the request carries the configured
+    /// [`shutdown_token`](crate::ConfigBuilder::shutdown_token), then runs the
+    /// shutdown hooks, sends 0 on the channel of a [`ShutdownStrategy::Callback`]
+    /// and gracefully stops the server registered through
+    /// [`Actuator::actix_server_handle`], or the current actix system when none
+    /// is registered. It neither exits nor signals the process. With the `gzip`
+    /// feature, the env and thread dump endpoints answer the requests accepting
+    /// gzip with compressed bodies.
+    pub fn actix_scope(&self) -> Scope {
+        let mut scope = web::scope(&self.inner.cfg.base_path).app_data(Data::new(self.clone()));
+        for endpoint in self.enabled_endpoints() {
+            let path = endpoint.path();
+            let route = match endpoint {
+                Endpoint::Ping => web::get().to(ping::<E>),
+                Endpoint::Info => web::get().to(info::<E>),
+                Endpoint::Health => web::get().to(health::<E>),
+                Endpoint::Env => web::get().to(env::<E>),
+                Endpoint::Metrics => web::get().to(metrics::<E>),
+                Endpoint::Shutdown => web::post().to(shutdown::<E>),
+                Endpoint::ThreadDump => web::get().to(thread_dump::<E>),
+            };
+            scope = if self.inner.cfg.basic_auth.contains_key(&endpoint) {
+                let actuator = self.clone();
+                let resource = web::resource(path)
+                    .wrap(middleware::from_fn(move |request, next| {
+                        authorize(actuator.clone(), endpoint, request, next)
+                    }))
+                    .route(route);
+                scope.service(resource)
+            } else {
+                scope.route(path, route)
+            };
+        }
+        let index_path = if self.inner.cfg.base_path.is_empty() {
+            "/"
+        } else {
+            ""
+        };
+        scope = scope.route(index_path, web::get().to(index::<E>));
+        for (path, handler) in self.custom_endpoints() {
+            scope = scope.route(
+                &path,
+                web::get().to(move || {
+                    let handler = handler.clone();
+                    async move { HttpResponse::Ok().body(handler()) }
+                }),
+            );
+        }
+        scope
+    }
+
+    /// Registers the handle of the server the scope is served by, so the
+    /// shutdown endpoint can stop it gracefully. Only the first handle is kept.
+    pub fn actix_server_handle(&self, handle: ServerHandle) {
+        let _ = self.inner.actix_server.set(handle);
+    }
+}
+
+async fn authorize<E: Error + Send + 'static, B: MessageBody>(
+    actuator: Actuator<E>,
+    endpoint: Endpoint,
+    request: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, actix_web::Error> {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if !actuator.authorizes(endpoint, authorization) {
+        let response = HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, r#"Basic realm="actuator""#))
+            .finish();
+        return Ok(request.into_response(response).map_into_right_body());
+    }
+    Ok(next.call(request).await?.map_into_left_body())
+}
+
+async fn index<E: Error + Send + 'static>(actuator: Data<Actuator<E>>) -> HttpResponse {
+    HttpResponse::Ok().json(actuator.index())
+}
+
+async fn ping<E: Error + Send + 'static>(actuator: Data<Actuator<E>>) -> HttpResponse {
+    HttpResponse::Ok().body(actuator.ping_response())
+}
+
+async fn info<E: Error + Send + 'static>(
+    actuator: Data<Actuator<E>>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let etag = actuator.info_etag();
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if etag_matches(&etag, if_none_match) {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish();
+    }
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .json(actuator.info())
+}
+
+async fn health<E: Error + Send + 'static>(actuator: Data<Actuator<E>>) -> HttpResponse {
+    let report = actuator.health().await;
+    let status = StatusCode::from_u16(actuator.health_status_code(report.status))
+        .expect("status codes are validated when building the config");
+    HttpResponse::build(status).json(report)
+}
+
+async fn env<E: Error + Send + 'static>(
+    actuator: Data<Actuator<E>>,
+    request: HttpRequest,
+) -> HttpResponse {
+    encoded_json(&actuator.env(), &request)
+}
+
+async fn metrics<E: Error + Send + 'static>(actuator: Data<Actuator<E>>) -> HttpResponse {
+    match actuator.metrics_async().await {
+        Ok(metrics) => HttpResponse::Ok().json(metrics),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+async fn shutdown<E: Error + Send + 'static>(
+    actuator: Data<Actuator<E>>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let header = request
+        .headers()
+        .get(SHUTDOWN_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let query = Some(request.query_string()).filter(|query| !query.is_empty());
+    if !actuator.confirms_shutdown(shutdown_token(header, query).as_deref()) {
+        return HttpResponse::Forbidden().finish();
+    }
+    let system = System::current();
+    let actuator = actuator.into_inner();
+    actix_web::rt::spawn(async move {
+        let handle = actuator.inner.actix_server.get().cloned();
+        let _ = web::block(move || {
+            actuator.run_shutdown_hooks();
+            if let ShutdownStrategy::Callback(shutdown) = &actuator.inner.cfg.shutdown_strategy {
+                let _ = shutdown.send(0);
+            }
+        })
+        .await;
+        match handle {
+            Some(handle) => handle.stop(true).await,
+            None => system.stop(),
+        }
+    });
+    HttpResponse::Accepted().finish()
+}
+
+async fn thread_dump<E: Error + Send + 'static>(
+    actuator: Data<Actuator<E>>,
+    request: HttpRequest,
+) -> HttpResponse {
+    encoded_json(&actuator.thread_dump_async().await, &request)
+}
+
+/// Answers with the value as JSON, encoded as the request accepts.
+fn encoded_json(value: &impl Serialize, request: &HttpRequest) -> HttpResponse {
+    let accept_encoding = request
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok());
+    let Ok(body) = json_body(value, accept_encoding) else {
+        return HttpResponse::InternalServerError().finish();
+    };
+    let mut response = HttpResponse::Ok();
+    response.content_type("application/json");
+    if let Some(encoding) = body.encoding {
+        response
+            .insert_header((header::CONTENT_ENCODING, encoding))
+            .insert_header((header::VARY, "accept-encoding"));
+    }
+    response.body(body.bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use actix_web::{App, HttpServer, test};
+    use futures::FutureExt;
+    use futures::future::BoxFuture;
+
+    use crate::{
+        ActuatorError, BasicAuth, Config, HealthCheckFn, HealthChecker, HealthConfig, HealthGroup,
+        HealthStatus,
+    };
+
+    use super::*;
+
+    fn pass() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Ok(()) }.boxed()
+    }
+
+    fn fail() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Err(ActuatorError::new("connection refused")) }.boxed()
+    }
+
+    fn actuator(func: HealthCheckFn<ActuatorError>) -> Actuator {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Ping, Endpoint::Health])
+            .health(
+                HealthConfig::default().checker(
+                    HealthChecker::new("db", crate::health_check_fn(func))
+                        .group(HealthGroup::Liveness)
+                        .group(HealthGroup::Readiness),
+                ),
+            )
+            .build()
+            .unwrap();
+        Actuator::new(cfg)
+    }
+
+    async fn status(actuator: Actuator, uri: &str) -> StatusCode {
+        let app = test::init_service(App::new().service(actuator.actix_scope())).await;
+        let request = test::TestRequest::get().uri(uri).to_request();
+        test::call_service(&app, request).await.status()
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_token() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .shutdown_token("s3cret")
+            .build()
+            .unwrap();
+        let app = test::init_service(App::new().service(Actuator::new(cfg).actix_scope())).await;
+        for request in [
+            test::TestRequest::post().uri("/actuator/shutdown"),
+            test::TestRequest::post().uri("/actuator/shutdown?token=wrong"),
+            test::TestRequest::post()
+                .uri("/actuator/shutdown")
+                .insert_header((SHUTDOWN_TOKEN_HEADER, "wrong")),
+        ] {
+            let response = test::call_service(&app, request.to_request()).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_stops_server() {
+        let exits = Arc::new(AtomicUsize::new(0));
+        let counter = exits.clone();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .shutdown_token("s3cret")
+            .with_exit_handler(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let hooks = Arc::new(AtomicUsize::new(0));
+        let ran = hooks.clone();
+        actuator.on_shutdown(Box::new(move || {
+            ran.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let response = shutdown_over_http(&actuator).await;
+        assert!(response.starts_with("HTTP/1.1 202"), "{response}");
+        assert_eq!(hooks.load(Ordering::SeqCst), 1);
+        assert_eq!(exits.load(Ordering::SeqCst), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_notifies_callback() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .shutdown_token("s3cret")
+            .shutdown_strategy(ShutdownStrategy::Callback(tx))
+            .build()
+            .unwrap();
+        let response = shutdown_over_http(&Actuator::new(cfg)).await;
+        assert!(response.starts_with("HTTP/1.1 202"), "{response}");
+        assert_eq!(rx.try_recv(), Ok(0));
+    }
+
+    /// Serves the actuator, requests the shutdown with the `s3cret` token and
+    /// waits for the server to stop, answering the raw response.
+    async fn shutdown_over_http(actuator: &Actuator) -> String {
+        let scoped = actuator.clone();
+        let server = HttpServer::new(move || App::new().service(scoped.actix_scope()))
+            .workers(1)
+            .disable_signals()
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        actuator.actix_server_handle(server.handle());
+        let server = actix_web::rt::spawn(server);
+
+        let response = web::block(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream
+                .write_all(
+                    b"POST /actuator/shutdown HTTP/1.1\r\nHost: localhost\r\n\
+                      X-Shutdown-Token: s3cret\r\nContent-Length: 0\r\n\
+                      Connection: close\r\n\r\n",
+                )
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+        .await
+        .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server stopped")
+            .unwrap()
+            .unwrap();
+        response
+    }
+
+    #[actix_web::test]
+    async fn test_health_up() {
+        assert_eq!(
+            status(actuator(pass), "/actuator/health").await,
+            StatusCode::OK
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_health_down() {
+        assert_eq!(
+            status(actuator(fail), "/actuator/health").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_health_status_code() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Health])
+            .health_status_code(HealthStatus::Degraded, 503)
+            .health(HealthConfig::default().checker(
+                HealthChecker::new("cache", crate::health_check_fn(fail)).mandatory(false),
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(
+            status(Actuator::new(cfg), "/actuator/health").await,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_metrics() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Metrics])
+            .build()
+            .unwrap();
+        let app = test::init_service(App::new().service(Actuator::new(cfg).actix_scope())).await;
+        let request = test::TestRequest::get()
+            .uri("/actuator/metrics")
+            .to_request();
+        let json: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert!(json["total_memory"].as_u64() > Some(0));
+    }
+
+    #[actix_web::test]
+    async fn test_health_body() {
+        let app = test::init_service(App::new().service(actuator(fail).actix_scope())).await;
+        let request = test::TestRequest::get()
+            .uri("/actuator/health")
+            .to_request();
+        let json: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(json["checks"]["db"]["error"], "connection refused");
+    }
+
+    #[actix_web::test]
+    async fn test_info_not_modified() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Info])
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let etag = actuator.info_etag();
+        let app = test::init_service(App::new().service(actuator.actix_scope())).await;
+        let request = test::TestRequest::get()
+            .uri("/actuator/info")
+            .insert_header((header::IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), etag.as_str());
+        let request = test::TestRequest::get().uri("/actuator/info").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), etag.as_str());
+    }
+
+    #[actix_web::test]
+    async fn test_base_path() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Ping])
+            .base_path("/ops")
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        assert_eq!(status(actuator.clone(), "/ops/ping").await, StatusCode::OK);
+        assert_eq!(
+            status(actuator, "/actuator/ping").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_basic_auth() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Info, Endpoint::Env])
+            .basic_auth(Endpoint::Env, BasicAuth::new("admin", "s3cret"))
+            .build()
+            .unwrap();
+        let app = test::init_service(App::new().service(Actuator::new(cfg).actix_scope())).await;
+        let env = |authorization: Option<&str>| {
+            let mut request = test::TestRequest::get().uri("/actuator/env");
+            if let Some(authorization) = authorization {
+                request = request.insert_header((header::AUTHORIZATION, authorization));
+            }
+            request.to_request()
+        };
+
+        let response = test::call_service(&app, env(None)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            r#"Basic realm="actuator""#
+        );
+        let response = test::call_service(&app, env(Some("Basic YWRtaW46d3Jvbmc="))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = test::call_service(&app, env(Some("Basic YWRtaW46czNjcmV0"))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = test::TestRequest::get().uri("/actuator/info").to_request();
+        assert_eq!(
+            test::call_service(&app, request).await.status(),
+            StatusCode::OK
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_index() {
+        let app = test::init_service(App::new().service(actuator(pass).actix_scope())).await;
+        let request = test::TestRequest::get().uri("/actuator").to_request();
+        let json: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(
+            json,
+            serde_json::json!({"ping": "/ping", "health": "/health"})
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_custom_endpoint() {
+        let actuator = actuator(pass);
+        actuator
+            .register_endpoint(
+                "version-detail".to_string(),
+                Box::new(|| "1.0.0 (8f3c2a1)".to_string()),
+            )
+            .unwrap();
+        let app = test::init_service(App::new().service(actuator.actix_scope())).await;
+        let request = test::TestRequest::get()
+            .uri("/actuator/version-detail")
+            .to_request();
+        assert_eq!(
+            test::call_and_read_body(&app, request).await,
+            "1.0.0 (8f3c2a1)"
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[actix_web::test]
+    async fn test_gzip() {
+        use std::io::Read;
+
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Env])
+            .env_prefix("CARGO_PKG_")
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let app = test::init_service(App::new().service(actuator.actix_scope())).await;
+        let request = test::TestRequest::get()
+            .uri("/actuator/env")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(
+            response.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+        let body = test::read_body(response).await;
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::to_value(actuator.env()).unwrap()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_disabled_endpoint() {
+        assert_eq!(
+            status(actuator(pass), "/actuator/env").await,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
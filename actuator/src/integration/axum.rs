@@ -0,0 +1,569 @@
+use std::error::Error;
+
+use axum::extract::{RawQuery, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+
+use crate::integration::{
+    SHUTDOWN_TOKEN_HEADER, etag_matches, json_body, shutdown_token, spawn_shutdown,
+};
+use crate::{Actuator, Endpoint};
+
+impl<E: Error + Send + Sync + 'static> Actuator<E> {
+    /// Builds an [`axum::Router`] serving the enabled endpoints under the
+    /// configured base path, requests to the disabled ones are answered with 404.
+    /// Requests to the endpoints requiring basic authentication are answered
+    /// with 401 unless they carry the credentials. The base path itself serves
+    /// the [`Actuator::index`].
+    ///
+    /// The ping endpoint answers with the configured payload as is, the other
+    /// ones with JSON. The health endpoint answers with the
+    /// [`Actuator::health_status_code`] of the status. The info endpoint answers
+    /// with 304 when `If-None-Match` carries the [`Actuator::info_etag`]. The
+    /// shutdown endpoint answers with 403 unless the request carries the
+    /// configured [`shutdown_token`](crate::ConfigBuilder::shutdown_token),
+    /// with 202 otherwise before shutting down on the blocking thread pool.
+    /// With the `gzip` feature, the env and thread dump endpoints answer the
+    /// requests accepting gzip with compressed bodies.
+    pub fn router(&self) -> Router {
+        let mut router = Router::new();
+        for endpoint in self.enabled_endpoints() {
+            let path = format!("{}{}", self.inner.cfg.base_path, endpoint.path());
+            let mut route = match endpoint {
+                Endpoint::Ping => get(ping::<E>),
+                Endpoint::Info => get(info::<E>),
+                Endpoint::Health => get(health::<E>),
+                Endpoint::Env => get(env::<E>),
+                Endpoint::Metrics => get(metrics::<E>),
+                Endpoint::Shutdown => post(shutdown::<E>),
+                Endpoint::ThreadDump => get(thread_dump::<E>),
+            };
+            if self.inner.cfg.basic_auth.contains_key(&endpoint) {
+                route = route.route_layer(middleware::from_fn_with_state(
+                    (self.clone(), endpoint),
+                    authorize::<E>,
+                ));
+            }
+            router = router.route(&path, route);
+        }
+        for (path, handler) in self.custom_endpoints() {
+            let path = format!("{}{path}", self.inner.cfg.base_path);
+            router = router.route(
+                &path,
+                get(move || {
+                    let handler = handler.clone();
+                    async move { handler() }
+                }),
+            );
+        }
+        let base_path = match self.inner.cfg.base_path.as_str() {
+            "" => "/",
+            base_path => base_path,
+        };
+        router = router.route(base_path, get(index::<E>));
+        router.with_state(self.clone())
+    }
+}
+
+async fn authorize<E: Error + Send + 'static>(
+    State((actuator, endpoint)): State<(Actuator<E>, Endpoint)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if !actuator.authorizes(endpoint, authorization) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, r#"Basic realm="actuator""#)],
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+async fn index<E: Error + Send + 'static>(State(actuator): State<Actuator<E>>) -> Response {
+    Json(actuator.index()).into_response()
+}
+
+async fn ping<E: Error + Send + 'static>(State(actuator): State<Actuator<E>>) -> Response {
+    actuator.ping_response().into_response()
+}
+
+async fn info<E: Error + Send + 'static>(
+    State(actuator): State<Actuator<E>>,
+    headers: HeaderMap,
+) -> Response {
+    let etag = actuator.info_etag();
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if etag_matches(&etag, if_none_match) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+    ([(header::ETAG, etag)], Json(actuator.info())).into_response()
+}
+
+async fn health<E: Error + Send + 'static>(State(actuator): State<Actuator<E>>) -> Response {
+    let report = actuator.health().await;
+    let status = StatusCode::from_u16(actuator.health_status_code(report.status))
+        .expect("status codes are validated when building the config");
+    (status, Json(report)).into_response()
+}
+
+async fn env<E: Error + Send + 'static>(
+    State(actuator): State<Actuator<E>>,
+    headers: HeaderMap,
+) -> Response {
+    encoded_json(&actuator.env(), &headers)
+}
+
+async fn metrics<E: Error + Send + 'static>(State(actuator): State<Actuator<E>>) -> Response {
+    match actuator.metrics_async().await {
+        Ok(metrics) => Json(metrics).into_response(),
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
+}
+
+async fn shutdown<E: Error + Send + 'static>(
+    State(actuator): State<Actuator<E>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Response {
+    let header = headers
+        .get(SHUTDOWN_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok());
+    if !actuator.confirms_shutdown(shutdown_token(header, query.as_deref()).as_deref()) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    spawn_shutdown(actuator);
+    StatusCode::ACCEPTED.into_response()
+}
+
+async fn thread_dump<E: Error + Send + 'static>(
+    State(actuator): State<Actuator<E>>,
+    headers: HeaderMap,
+) -> Response {
+    encoded_json(&actuator.thread_dump_async().await, &headers)
+}
+
+/// Answers with the value as JSON, encoded as the request accepts.
+fn encoded_json(value: &impl Serialize, headers: &HeaderMap) -> Response {
+    let accept_encoding = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok());
+    let Ok(body) = json_body(value, accept_encoding) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mut response = ([(header::CONTENT_TYPE, "application/json")], body.bytes).into_response();
+    if let Some(encoding) = body.encoding {
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use futures::FutureExt;
+    use futures::future::BoxFuture;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::{
+        ActuatorError, BasicAuth, Config, HealthChecker, HealthConfig, HealthGroup, HealthStatus,
+    };
+
+    use super::*;
+
+    fn pass() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Ok(()) }.boxed()
+    }
+
+    fn fail() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Err(ActuatorError::new("connection refused")) }.boxed()
+    }
+
+    fn router(db: HealthChecker) -> Router {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Ping, Endpoint::Info, Endpoint::Health])
+            .health(HealthConfig::default().checker(db))
+            .build()
+            .unwrap();
+        Actuator::new(cfg).router()
+    }
+
+    fn db(func: crate::HealthCheckFn<ActuatorError>) -> HealthChecker {
+        HealthChecker::new("db", crate::health_check_fn(func))
+            .group(HealthGroup::Liveness)
+            .group(HealthGroup::Readiness)
+    }
+
+    async fn call(router: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let response = router(db(pass))
+            .oneshot(Request::get("/actuator/ping").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "pong");
+    }
+
+    #[tokio::test]
+    async fn test_info() {
+        let (status, json) = call(router(db(pass)), "/actuator/info").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["application"]["name"], "orders");
+    }
+
+    #[tokio::test]
+    async fn test_info_not_modified() {
+        let router = router(db(pass));
+        let response = router
+            .clone()
+            .oneshot(Request::get("/actuator/info").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let etag = response.headers()[header::ETAG].clone();
+        let request = |if_none_match| {
+            Request::get("/actuator/info")
+                .header(header::IF_NONE_MATCH, if_none_match)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = router.clone().oneshot(request(etag.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+        let response = router
+            .oneshot(request(header::HeaderValue::from_static(r#"W/"0""#)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_health_up() {
+        let (status, json) = call(router(db(pass)), "/actuator/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "UP");
+        assert_eq!(json["checks"]["db"]["success"], true);
+        assert!(json["checked_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_health_down() {
+        let (status, json) = call(router(db(fail)), "/actuator/health").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["checks"]["db"]["error"], "connection refused");
+    }
+
+    #[tokio::test]
+    async fn test_health_degraded() {
+        let mut cache = db(fail);
+        cache.key = "cache".to_string();
+        cache.is_mandatory = false;
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Health])
+            .health(HealthConfig::default().checker(db(pass)).checker(cache))
+            .build()
+            .unwrap();
+        let (status, json) = call(Actuator::new(cfg).router(), "/actuator/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["status"], "DEGRADED");
+        assert_eq!(json["checks"]["cache"]["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_health_status_code() {
+        let cache = HealthChecker::new("cache", crate::health_check_fn(fail)).mandatory(false);
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Health])
+            .health_status_code(HealthStatus::Degraded, 503)
+            .health(HealthConfig::default().checker(db(pass)).checker(cache))
+            .build()
+            .unwrap();
+        let (status, json) = call(Actuator::new(cfg).router(), "/actuator/health").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["status"], "DEGRADED");
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Metrics])
+            .build()
+            .unwrap();
+        let (status, json) = call(Actuator::new(cfg).router(), "/actuator/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(json["total_memory"].as_u64() > Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let exits = Arc::new(AtomicUsize::new(0));
+        let counter = exits.clone();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .with_exit_handler(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+        let response = Actuator::new(cfg)
+            .router()
+            .oneshot(
+                Request::post("/actuator/shutdown")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        wait_for_exits(&exits, 1).await;
+    }
+
+    async fn wait_for_exits(exits: &AtomicUsize, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while exits.load(Ordering::SeqCst) < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(exits.load(Ordering::SeqCst), count);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_answers_before_the_hooks() {
+        let (exit, exited) = std::sync::mpsc::channel();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .with_exit_handler(move |code| exit.send(code).unwrap())
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        actuator.on_shutdown(Box::new(move || {
+            let _ = released.recv();
+        }));
+        let request = Request::post("/actuator/shutdown")
+            .body(Body::empty())
+            .unwrap();
+        let response =
+            tokio::time::timeout(Duration::from_secs(5), actuator.router().oneshot(request))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert!(exited.try_recv().is_err());
+        drop(release);
+        assert_eq!(exited.recv_timeout(Duration::from_secs(5)), Ok(0));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_token() {
+        let exits = Arc::new(AtomicUsize::new(0));
+        let counter = exits.clone();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .shutdown_token("s3cret")
+            .with_exit_handler(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+        let router = Actuator::new(cfg).router();
+        for request in [
+            Request::post("/actuator/shutdown"),
+            Request::post("/actuator/shutdown?token=wrong"),
+            Request::post("/actuator/shutdown").header(SHUTDOWN_TOKEN_HEADER, "wrong"),
+        ] {
+            let request = request.body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+        assert_eq!(exits.load(Ordering::SeqCst), 0);
+
+        for request in [
+            Request::post("/actuator/shutdown?token=s3cret"),
+            Request::post("/actuator/shutdown").header(SHUTDOWN_TOKEN_HEADER, "s3cret"),
+        ] {
+            let request = request.body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::ACCEPTED);
+        }
+        wait_for_exits(&exits, 2).await;
+    }
+
+    #[tokio::test]
+    async fn test_base_path() {
+        for (base_path, uri) in [("/ops/actuator", "/ops/actuator/info"), ("", "/info")] {
+            let cfg = Config::builder()
+                .name("orders")
+                .port(8080)
+                .endpoints([Endpoint::Info])
+                .base_path(base_path)
+                .build()
+                .unwrap();
+            let router = Actuator::new(cfg).router();
+            let (status, json) = call(router.clone(), uri).await;
+            assert_eq!(status, StatusCode::OK, "{uri}");
+            assert_eq!(json["application"]["name"], "orders");
+            let (status, _) = call(router, "/actuator/info").await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{base_path:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Info, Endpoint::Env])
+            .basic_auth(Endpoint::Env, BasicAuth::new("admin", "s3cret"))
+            .build()
+            .unwrap();
+        let router = Actuator::new(cfg).router();
+        let env = |authorization: Option<&str>| {
+            let mut request = Request::get("/actuator/env");
+            if let Some(authorization) = authorization {
+                request = request.header(header::AUTHORIZATION, authorization);
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = env(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Basic realm="actuator""#
+        );
+        let response = env(Some("Basic YWRtaW46d3Jvbmc=")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = env(Some("Basic YWRtaW46czNjcmV0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (status, _) = call(router, "/actuator/info").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_index() {
+        let (status, json) = call(router(db(pass)), "/actuator").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            json,
+            serde_json::json!({"ping": "/ping", "info": "/info", "health": "/health"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_endpoint() {
+        let cfg = Config::builder().name("orders").port(8080).build().unwrap();
+        let actuator = Actuator::new(cfg);
+        actuator
+            .register_endpoint(
+                "version-detail".to_string(),
+                Box::new(|| "1.0.0 (8f3c2a1)".to_string()),
+            )
+            .unwrap();
+        let response = actuator
+            .router()
+            .oneshot(
+                Request::get("/actuator/version-detail")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "1.0.0 (8f3c2a1)");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_gzip() {
+        use std::io::Read;
+
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Env])
+            .env_prefix("CARGO_PKG_")
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let request = Request::get("/actuator/env")
+            .header(header::ACCEPT_ENCODING, "gzip, deflate")
+            .body(Body::empty())
+            .unwrap();
+        let response = actuator.router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::to_value(actuator.env()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_endpoint() {
+        for uri in [
+            "/actuator/env",
+            "/actuator/metrics",
+            "/actuator/shutdown",
+            "/actuator/threaddump",
+        ] {
+            let (status, _) = call(router(db(pass)), uri).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+}
@@ -0,0 +1,558 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, header};
+use http_body_util::Full;
+use serde::Serialize;
+
+use crate::integration::{
+    SHUTDOWN_TOKEN_HEADER, etag_matches, json_body, shutdown_token, spawn_shutdown,
+};
+use crate::{Actuator, Endpoint, EndpointHandler};
+
+/// [`tower_service::Service`] serving the enabled endpoints of an actuator under
+/// the configured base path, created through [`Actuator::service`].
+///
+/// Requests are dispatched by path and method, the body is ignored. Requests to
+/// the disabled endpoints are answered with 404, the ones with another method
+/// with 405. To serve it from a hyper server, wrap it in the
+/// `TowerToHyperService` adapter of `hyper-util`.
+pub struct ActuatorService<E = crate::ActuatorError> {
+    actuator: Actuator<E>,
+    /// Endpoints registered by the time the service was built.
+    custom_endpoints: Arc<Vec<(String, Arc<EndpointHandler>)>>,
+}
+
+impl<E> Clone for ActuatorService<E> {
+    fn clone(&self) -> Self {
+        Self {
+            actuator: self.actuator.clone(),
+            custom_endpoints: self.custom_endpoints.clone(),
+        }
+    }
+}
+
+impl<E: Error + Send + Sync + 'static> Actuator<E> {
+    /// Builds a [`tower_service::Service`] answering like the other framework
+    /// integrations: the ping endpoint with the configured payload as is, the
+    /// other ones with JSON, the health endpoint with the
+    /// [`Actuator::health_status_code`] of the status, and the endpoints
+    /// requiring basic authentication with 401 unless the request carries the
+    /// credentials. The base path itself serves the [`Actuator::index`], the
+    /// info endpoint answers with 304 when `If-None-Match` carries the
+    /// [`Actuator::info_etag`] and the shutdown endpoint with 403 unless the
+    /// request carries the configured
+    /// [`shutdown_token`](crate::ConfigBuilder::shutdown_token), with 202
+    /// otherwise before shutting down on the blocking thread pool. With the
+    /// `gzip` feature, the env and thread dump endpoints answer the requests
+    /// accepting gzip with compressed bodies.
+    pub fn service(&self) -> ActuatorService<E> {
+        ActuatorService {
+            actuator: self.clone(),
+            custom_endpoints: Arc::new(self.custom_endpoints()),
+        }
+    }
+}
+
+impl<E: Error + Send + Sync + 'static, B> tower_service::Service<Request<B>>
+    for ActuatorService<E>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let actuator = self.actuator.clone();
+        let Some(path) = request
+            .uri()
+            .path()
+            .strip_prefix(actuator.inner.cfg.base_path.as_str())
+        else {
+            return async { Ok(status(StatusCode::NOT_FOUND)) }.boxed();
+        };
+        let index_path = if actuator.inner.cfg.base_path.is_empty() {
+            "/"
+        } else {
+            ""
+        };
+        if path == index_path {
+            let response = match *request.method() {
+                Method::GET => json(StatusCode::OK, &actuator.index()),
+                _ => method_not_allowed(Method::GET),
+            };
+            return async { Ok(response) }.boxed();
+        }
+        let endpoint = actuator
+            .enabled_endpoints()
+            .into_iter()
+            .find(|endpoint| endpoint.path() == path);
+        let Some(endpoint) = endpoint else {
+            let custom = self
+                .custom_endpoints
+                .iter()
+                .find(|(custom, _)| custom == path);
+            let response = match custom {
+                Some(_) if request.method() != Method::GET => method_not_allowed(Method::GET),
+                Some((_, handler)) => text(handler()),
+                None => status(StatusCode::NOT_FOUND),
+            };
+            return async { Ok(response) }.boxed();
+        };
+        let method = match endpoint {
+            Endpoint::Shutdown => Method::POST,
+            _ => Method::GET,
+        };
+        if request.method() != method {
+            let response = method_not_allowed(method);
+            return async { Ok(response) }.boxed();
+        }
+        let authorization = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        if !actuator.authorizes(endpoint, authorization) {
+            let mut response = status(StatusCode::UNAUTHORIZED);
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(r#"Basic realm="actuator""#),
+            );
+            return async { Ok(response) }.boxed();
+        }
+        if endpoint == Endpoint::Shutdown {
+            let header = request
+                .headers()
+                .get(SHUTDOWN_TOKEN_HEADER)
+                .and_then(|value| value.to_str().ok());
+            if !actuator.confirms_shutdown(shutdown_token(header, request.uri().query()).as_deref())
+            {
+                return async { Ok(status(StatusCode::FORBIDDEN)) }.boxed();
+            }
+        }
+        let headers = request.headers().clone();
+        async move { Ok(respond(actuator, endpoint, headers).await) }.boxed()
+    }
+}
+
+async fn respond<E: Error + Send + 'static>(
+    actuator: Actuator<E>,
+    endpoint: Endpoint,
+    headers: HeaderMap,
+) -> Response<Full<Bytes>> {
+    match endpoint {
+        Endpoint::Ping => text(actuator.ping_response()),
+        Endpoint::Info => {
+            let etag = actuator.info_etag();
+            let if_none_match = headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok());
+            let mut response = match etag_matches(&etag, if_none_match) {
+                true => status(StatusCode::NOT_MODIFIED),
+                false => json(StatusCode::OK, &actuator.info()),
+            };
+            if let Ok(etag) = HeaderValue::from_str(&etag) {
+                response.headers_mut().insert(header::ETAG, etag);
+            }
+            response
+        }
+        Endpoint::Health => {
+            let report = actuator.health().await;
+            let status = StatusCode::from_u16(actuator.health_status_code(report.status))
+                .expect("status codes are validated when building the config");
+            json(status, &report)
+        }
+        Endpoint::Env => encoded_json(&actuator.env(), &headers),
+        Endpoint::Metrics => match actuator.metrics_async().await {
+            Ok(metrics) => json(StatusCode::OK, &metrics),
+            Err(_) => status(StatusCode::SERVICE_UNAVAILABLE),
+        },
+        Endpoint::Shutdown => {
+            spawn_shutdown(actuator);
+            status(StatusCode::ACCEPTED)
+        }
+        Endpoint::ThreadDump => encoded_json(&actuator.thread_dump_async().await, &headers),
+    }
+}
+
+/// Answers with the value as JSON, encoded as the request accepts.
+fn encoded_json(value: &impl Serialize, headers: &HeaderMap) -> Response<Full<Bytes>> {
+    let accept_encoding = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok());
+    let Ok(body) = json_body(value, accept_encoding) else {
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    let mut response = Response::new(Full::from(body.bytes));
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    if let Some(encoding) = body.encoding {
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    response
+}
+
+fn status(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::default());
+    *response.status_mut() = status;
+    response
+}
+
+fn method_not_allowed(allowed: Method) -> Response<Full<Bytes>> {
+    let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+    response.headers_mut().insert(
+        header::ALLOW,
+        HeaderValue::from_str(allowed.as_str()).unwrap(),
+    );
+    response
+}
+
+fn text(body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::from(body));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    response
+}
+
+fn json(status: StatusCode, value: &impl Serialize) -> Response<Full<Bytes>> {
+    match serde_json::to_vec(value) {
+        Ok(body) => {
+            let mut response = Response::new(Full::from(body));
+            *response.status_mut() = status;
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            response
+        }
+        Err(_) => self::status(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::future::BoxFuture;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use crate::{
+        ActuatorError, BasicAuth, Config, HealthCheckFn, HealthChecker, HealthConfig, HealthGroup,
+        HealthStatus,
+    };
+
+    use super::*;
+
+    fn pass() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Ok(()) }.boxed()
+    }
+
+    fn fail() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Err(ActuatorError::new("connection refused")) }.boxed()
+    }
+
+    fn service(func: HealthCheckFn<ActuatorError>) -> ActuatorService {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([
+                Endpoint::Ping,
+                Endpoint::Info,
+                Endpoint::Health,
+                Endpoint::Env,
+            ])
+            .basic_auth(Endpoint::Env, BasicAuth::new("admin", "s3cret"))
+            .health(
+                HealthConfig::default().checker(
+                    HealthChecker::new("db", crate::health_check_fn(func))
+                        .group(HealthGroup::Liveness)
+                        .group(HealthGroup::Readiness),
+                ),
+            )
+            .build()
+            .unwrap();
+        Actuator::new(cfg).service()
+    }
+
+    async fn call(service: ActuatorService, request: Request<()>) -> (StatusCode, Bytes) {
+        let response = service.oneshot(request).await.unwrap();
+        let status = response.status();
+        (
+            status,
+            response.into_body().collect().await.unwrap().to_bytes(),
+        )
+    }
+
+    fn get(uri: &str) -> Request<()> {
+        Request::get(uri).body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_status_code() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Health])
+            .health_status_code(HealthStatus::Degraded, 503)
+            .health(HealthConfig::default().checker(
+                HealthChecker::new("cache", crate::health_check_fn(fail)).mandatory(false),
+            ))
+            .build()
+            .unwrap();
+        let service = Actuator::new(cfg).service();
+        let (status, body) = call(service, get("/actuator/health")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "DEGRADED");
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Metrics])
+            .build()
+            .unwrap();
+        let service = Actuator::new(cfg).service();
+        let (status, body) = call(service, get("/actuator/metrics")).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["total_memory"].as_u64() > Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_token() {
+        let exits = Arc::new(AtomicUsize::new(0));
+        let counter = exits.clone();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .shutdown_token("s3cret")
+            .with_exit_handler(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+        let service = Actuator::new(cfg).service();
+        let shutdown = |uri: &str, token: Option<&str>| {
+            let mut request = Request::post(uri);
+            if let Some(token) = token {
+                request = request.header(SHUTDOWN_TOKEN_HEADER, token);
+            }
+            request.body(()).unwrap()
+        };
+
+        let request = shutdown("/actuator/shutdown", None);
+        assert_eq!(
+            call(service.clone(), request).await.0,
+            StatusCode::FORBIDDEN
+        );
+        let request = shutdown("/actuator/shutdown", Some("wrong"));
+        assert_eq!(
+            call(service.clone(), request).await.0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(exits.load(Ordering::SeqCst), 0);
+
+        let request = shutdown("/actuator/shutdown", Some("s3cret"));
+        assert_eq!(call(service.clone(), request).await.0, StatusCode::ACCEPTED);
+        let request = shutdown("/actuator/shutdown?token=s3cret", None);
+        assert_eq!(call(service, request).await.0, StatusCode::ACCEPTED);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while exits.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(exits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_answers_before_the_hooks() {
+        let (exit, exited) = std::sync::mpsc::channel();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .with_exit_handler(move |code| exit.send(code).unwrap())
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        actuator.on_shutdown(Box::new(move || {
+            let _ = released.recv();
+        }));
+        let request = Request::post("/actuator/shutdown").body(()).unwrap();
+        let (status, _) =
+            tokio::time::timeout(Duration::from_secs(5), call(actuator.service(), request))
+                .await
+                .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert!(exited.try_recv().is_err());
+        drop(release);
+        assert_eq!(exited.recv_timeout(Duration::from_secs(5)), Ok(0));
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let (status, body) = call(service(pass), get("/actuator/ping")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "pong");
+    }
+
+    #[tokio::test]
+    async fn test_info() {
+        let (status, body) = call(service(pass), get("/actuator/info")).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["application"]["name"], "orders");
+    }
+
+    #[tokio::test]
+    async fn test_info_not_modified() {
+        let service = service(pass);
+        let response = service
+            .clone()
+            .oneshot(get("/actuator/info"))
+            .await
+            .unwrap();
+        let etag = response.headers()[header::ETAG].clone();
+        let request = Request::get("/actuator/info")
+            .header(header::IF_NONE_MATCH, etag.clone())
+            .body(())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let (status, body) = call(service(pass), get("/actuator/health")).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], "UP");
+
+        let (status, body) = call(service(fail), get("/actuator/health")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["checks"]["db"]["error"], "connection refused");
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        let (status, _) = call(service(pass), get("/actuator/env")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let request = Request::get("/actuator/env")
+            .header(header::AUTHORIZATION, "Basic YWRtaW46czNjcmV0")
+            .body(())
+            .unwrap();
+        let (status, _) = call(service(pass), request).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_index() {
+        let (status, body) = call(service(pass), get("/actuator")).await;
+        assert_eq!(status, StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "ping": "/ping",
+                "info": "/info",
+                "health": "/health",
+                "env": "/env",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_endpoint() {
+        let cfg = Config::builder().name("orders").port(8080).build().unwrap();
+        let actuator = Actuator::new(cfg);
+        actuator
+            .register_endpoint(
+                "version-detail".to_string(),
+                Box::new(|| "1.0.0 (8f3c2a1)".to_string()),
+            )
+            .unwrap();
+        let (status, body) = call(actuator.service(), get("/actuator/version-detail")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "1.0.0 (8f3c2a1)");
+        let request = Request::post("/actuator/version-detail").body(()).unwrap();
+        let (status, _) = call(actuator.service(), request).await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_gzip() {
+        use std::io::Read;
+
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Env])
+            .env_prefix("CARGO_PKG_")
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let request = Request::get("/actuator/env")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(())
+            .unwrap();
+        let response = actuator.service().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::to_value(actuator.env()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_endpoint() {
+        for uri in [
+            "/actuator/metrics",
+            "/actuator/unknown",
+            "/ping",
+            "/actuator/",
+        ] {
+            let (status, _) = call(service(pass), get(uri)).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wrong_method() {
+        let request = Request::post("/actuator/ping").body(()).unwrap();
+        let response = service(pass).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[header::ALLOW], "GET");
+    }
+}
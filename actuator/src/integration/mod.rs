@@ -0,0 +1,285 @@
+//! Adapters serving the actuator endpoints through web frameworks.
+
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "hyper")]
+mod hyper;
+#[cfg(feature = "warp")]
+mod warp;
+
+#[cfg(feature = "hyper")]
+pub use hyper::ActuatorService;
+
+/// Serialized JSON body, with the `Content-Encoding` it was encoded with if
+/// any.
+#[cfg(any(
+    feature = "actix",
+    feature = "axum",
+    feature = "hyper",
+    feature = "warp"
+))]
+pub(crate) struct JsonBody {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) encoding: Option<&'static str>,
+}
+
+/// Serializes the value, then gzip encodes it when the `gzip` feature is
+/// enabled and the `Accept-Encoding` header, if any, accepts it.
+#[cfg(any(
+    feature = "actix",
+    feature = "axum",
+    feature = "hyper",
+    feature = "warp"
+))]
+pub(crate) fn json_body(
+    value: &impl serde::Serialize,
+    accept_encoding: Option<&str>,
+) -> serde_json::Result<JsonBody> {
+    let bytes = serde_json::to_vec(value)?;
+    #[cfg(feature = "gzip")]
+    if accepts_gzip(accept_encoding) {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        // Writing to a vector cannot fail.
+        if let Ok(gzipped) = encoder.write_all(&bytes).and_then(|_| encoder.finish()) {
+            return Ok(JsonBody {
+                bytes: gzipped,
+                encoding: Some("gzip"),
+            });
+        }
+    }
+    #[cfg(not(feature = "gzip"))]
+    let _ = accept_encoding;
+    Ok(JsonBody {
+        bytes,
+        encoding: None,
+    })
+}
+
+/// Reports whether the `Accept-Encoding` header lists gzip with a non-zero
+/// quality.
+#[cfg(all(
+    feature = "gzip",
+    any(
+        feature = "actix",
+        feature = "axum",
+        feature = "hyper",
+        feature = "warp"
+    )
+))]
+fn accepts_gzip(accept_encoding: Option<&str>) -> bool {
+    accept_encoding.is_some_and(|header| {
+        header.split(',').any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            let quality = params
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse::<f32>().ok());
+            name.eq_ignore_ascii_case("gzip") && quality.is_some_and(|quality| quality > 0.0)
+        })
+    })
+}
+
+/// Reports whether the `If-None-Match` header, if any, lists the ETag or is
+/// `*`, comparing weakly as required for GET requests.
+#[cfg(any(
+    feature = "actix",
+    feature = "axum",
+    feature = "hyper",
+    feature = "warp"
+))]
+pub(crate) fn etag_matches(etag: &str, if_none_match: Option<&str>) -> bool {
+    fn opaque(tag: &str) -> &str {
+        tag.strip_prefix("W/").unwrap_or(tag)
+    }
+    if_none_match.is_some_and(|header| {
+        header
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || opaque(tag) == opaque(etag))
+    })
+}
+
+/// Header a shutdown request can carry the confirmation token in.
+#[cfg(any(
+    feature = "actix",
+    feature = "axum",
+    feature = "hyper",
+    feature = "warp"
+))]
+pub(crate) const SHUTDOWN_TOKEN_HEADER: &str = "x-shutdown-token";
+
+/// Returns the shutdown confirmation token a request carries in the
+/// `X-Shutdown-Token` header, or else in the `token` query parameter, decoded
+/// from its percent-encoded form.
+#[cfg(any(
+    feature = "actix",
+    feature = "axum",
+    feature = "hyper",
+    feature = "warp"
+))]
+pub(crate) fn shutdown_token<'a>(
+    header: Option<&'a str>,
+    query: Option<&'a str>,
+) -> Option<std::borrow::Cow<'a, str>> {
+    if let Some(header) = header {
+        return Some(header.into());
+    }
+    let token = query?
+        .split('&')
+        .find_map(|param| param.strip_prefix("token="))?;
+    Some(percent_decode(token))
+}
+
+/// Decodes a query value, `+` standing for a space. Malformed escapes are kept
+/// as is and invalid UTF-8 is replaced.
+#[cfg(any(
+    feature = "actix",
+    feature = "axum",
+    feature = "hyper",
+    feature = "warp"
+))]
+fn percent_decode(value: &str) -> std::borrow::Cow<'_, str> {
+    if !value.contains(['%', '+']) {
+        return value.into();
+    }
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned().into()
+}
+
+/// How long a shutdown requested over HTTP waits for the response confirming
+/// it to be sent before ending the process, as Spring Boot does.
+#[cfg(any(feature = "axum", feature = "hyper", feature = "warp"))]
+pub(crate) const SHUTDOWN_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Shuts the actuator down on the blocking thread pool of Tokio after the
+/// [`SHUTDOWN_DELAY`], so that neither the hooks nor the exit stall the
+/// executor or cut off the response confirming the shutdown.
+#[cfg(any(feature = "axum", feature = "hyper", feature = "warp"))]
+pub(crate) fn spawn_shutdown<E: std::error::Error + Send + 'static>(actuator: crate::Actuator<E>) {
+    tokio::task::spawn_blocking(move || {
+        std::thread::sleep(SHUTDOWN_DELAY);
+        actuator.shutdown();
+    });
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "actix",
+        feature = "axum",
+        feature = "hyper",
+        feature = "warp"
+    )
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = r#"W/"8f3c2a1""#;
+        assert!(etag_matches(etag, Some(r#"W/"8f3c2a1""#)));
+        assert!(etag_matches(etag, Some(r#""8f3c2a1""#)));
+        assert!(etag_matches(etag, Some(r#""0", W/"8f3c2a1""#)));
+        assert!(etag_matches(etag, Some("*")));
+        assert!(!etag_matches(etag, Some(r#"W/"0""#)));
+        assert!(!etag_matches(etag, Some("")));
+        assert!(!etag_matches(etag, None));
+    }
+
+    #[test]
+    fn test_shutdown_token() {
+        let token = |header, query| shutdown_token(header, query).map(String::from);
+        assert_eq!(token(Some("s3cret"), None).as_deref(), Some("s3cret"));
+        assert_eq!(
+            token(Some("s3cret"), Some("token=other")).as_deref(),
+            Some("s3cret")
+        );
+        assert_eq!(token(None, Some("token=s3cret")).as_deref(), Some("s3cret"));
+        assert_eq!(
+            token(None, Some("force=1&token=s3cret")).as_deref(),
+            Some("s3cret")
+        );
+        assert_eq!(token(None, Some("token=")).as_deref(), Some(""));
+        assert_eq!(token(None, Some("tokens=s3cret")), None);
+        assert_eq!(token(None, Some("")), None);
+        assert_eq!(token(None, None), None);
+    }
+
+    #[test]
+    fn test_shutdown_token_percent_decoded() {
+        let token = |query| shutdown_token(None, Some(query)).map(String::from);
+        assert_eq!(token("token=s%3Dc%26r+t%2b").as_deref(), Some("s=c&r t+"));
+        assert_eq!(token("token=%C3%A9").as_deref(), Some("é"));
+        assert_eq!(token("token=100%").as_deref(), Some("100%"));
+        assert_eq!(token("token=%zz%4").as_deref(), Some("%zz%4"));
+        assert_eq!(token("token=%FF").as_deref(), Some("\u{fffd}"));
+        assert_eq!(
+            shutdown_token(Some("a%20b"), None).as_deref(),
+            Some("a%20b")
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip(Some("gzip")));
+        assert!(accepts_gzip(Some("deflate, GZIP;q=0.5")));
+        assert!(accepts_gzip(Some("br;q=1.0, gzip; q=0.1")));
+        assert!(!accepts_gzip(Some("gzip;q=0")));
+        assert!(!accepts_gzip(Some("deflate, br")));
+        assert!(!accepts_gzip(Some("")));
+        assert!(!accepts_gzip(None));
+    }
+
+    #[test]
+    fn test_json_body() {
+        let value = serde_json::json!({"PATH": "/usr/bin"});
+        let body = json_body(&value, None).unwrap();
+        assert_eq!(body.bytes, serde_json::to_vec(&value).unwrap());
+        assert_eq!(body.encoding, None);
+
+        let body = json_body(&value, Some("gzip")).unwrap();
+        #[cfg(feature = "gzip")]
+        {
+            use std::io::Read;
+
+            assert_eq!(body.encoding, Some("gzip"));
+            let mut json = String::new();
+            flate2::read::GzDecoder::new(body.bytes.as_slice())
+                .read_to_string(&mut json)
+                .unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+                value
+            );
+        }
+        #[cfg(not(feature = "gzip"))]
+        assert_eq!(body.encoding, None);
+    }
+}
@@ -0,0 +1,487 @@
+use std::convert::Infallible;
+use std::error::Error;
+
+use serde::Serialize;
+use warp::filters::BoxedFilter;
+use warp::filters::path::FullPath;
+use warp::http::{HeaderMap, HeaderValue, StatusCode, header};
+use warp::reply::{self, Reply, Response};
+use warp::{Filter, Rejection};
+
+use crate::integration::{
+    SHUTDOWN_TOKEN_HEADER, etag_matches, json_body, shutdown_token, spawn_shutdown,
+};
+use crate::{Actuator, Endpoint};
+
+impl<E: Error + Send + Sync + 'static> Actuator<E> {
+    /// Builds a [`warp::Filter`] serving the enabled endpoints under the
+    /// configured base path, requests to the disabled ones are rejected as not
+    /// found.
+    ///
+    /// The ping endpoint answers with the configured payload as is, the other
+    /// ones with JSON. The health endpoint answers with the
+    /// [`Actuator::health_status_code`] of the status. Requests to the endpoints
+    /// requiring basic authentication are answered with 401 unless they carry
+    /// the credentials. The base path itself serves the [`Actuator::index`]. The
+    /// info endpoint answers with 304 when `If-None-Match` carries the
+    /// [`Actuator::info_etag`]. The shutdown endpoint answers with 403 unless the
+    /// request carries the configured
+    /// [`shutdown_token`](crate::ConfigBuilder::shutdown_token), with 202
+    /// otherwise before shutting down on the blocking thread pool. With the
+    /// `gzip` feature, the env and thread dump endpoints answer the requests
+    /// accepting gzip with compressed bodies.
+    pub fn warp_filter(
+        &self,
+    ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone + Send + Sync + 'static
+    {
+        let mut filter = warp::any()
+            .and_then(|| async { Err::<Response, _>(warp::reject::not_found()) })
+            .boxed();
+        for endpoint in self.enabled_endpoints() {
+            let path = format!("{}{}", self.inner.cfg.base_path, endpoint.path());
+            let method: BoxedFilter<()> = match endpoint {
+                Endpoint::Shutdown => warp::post().boxed(),
+                _ => warp::get().boxed(),
+            };
+            let actuator = self.clone();
+            let route = exact_path(path)
+                .and(method)
+                .and(warp::header::optional::<String>(
+                    header::AUTHORIZATION.as_str(),
+                ))
+                .and(warp::header::headers_cloned())
+                .and(raw_query())
+                .then(
+                    move |authorization: Option<String>,
+                          headers: HeaderMap,
+                          query: Option<String>| {
+                        respond(actuator.clone(), endpoint, authorization, headers, query)
+                    },
+                );
+            filter = route.or(filter).unify().boxed();
+        }
+        let index_path = match self.inner.cfg.base_path.as_str() {
+            "" => "/".to_string(),
+            base_path => base_path.to_string(),
+        };
+        let actuator = self.clone();
+        let index = exact_path(index_path)
+            .and(warp::get())
+            .map(move || reply::json(&actuator.index()).into_response());
+        filter = index.or(filter).unify().boxed();
+        for (path, handler) in self.custom_endpoints() {
+            let path = format!("{}{path}", self.inner.cfg.base_path);
+            let route = exact_path(path)
+                .and(warp::get())
+                .map(move || handler().into_response());
+            filter = route.or(filter).unify().boxed();
+        }
+        filter
+    }
+}
+
+/// Matches the requests to the path only, unlike [`warp::path`] which matches
+/// segment by segment.
+fn exact_path(path: String) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path::full()
+        .and_then(move |full: FullPath| {
+            let found = full.as_str() == path;
+            async move { found.then_some(()).ok_or_else(warp::reject::not_found) }
+        })
+        .untuple_one()
+}
+
+/// Extracts the query string of the request, if any, unlike
+/// [`warp::query::raw`] which rejects the requests without one.
+fn raw_query() -> impl Filter<Extract = (Option<String>,), Error = Infallible> + Clone {
+    warp::query::raw()
+        .map(Some)
+        .or(warp::any().map(|| None))
+        .unify()
+}
+
+async fn respond<E: Error + Send + 'static>(
+    actuator: Actuator<E>,
+    endpoint: Endpoint,
+    authorization: Option<String>,
+    headers: HeaderMap,
+    query: Option<String>,
+) -> Response {
+    if !actuator.authorizes(endpoint, authorization.as_deref()) {
+        return reply::with_header(
+            StatusCode::UNAUTHORIZED,
+            header::WWW_AUTHENTICATE,
+            r#"Basic realm="actuator""#,
+        )
+        .into_response();
+    }
+    match endpoint {
+        Endpoint::Ping => actuator.ping_response().into_response(),
+        Endpoint::Info => {
+            let etag = actuator.info_etag();
+            let if_none_match = headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|value| value.to_str().ok());
+            if etag_matches(&etag, if_none_match) {
+                return reply::with_header(StatusCode::NOT_MODIFIED, header::ETAG, etag)
+                    .into_response();
+            }
+            reply::with_header(reply::json(&actuator.info()), header::ETAG, etag).into_response()
+        }
+        Endpoint::Health => {
+            let report = actuator.health().await;
+            let status = StatusCode::from_u16(actuator.health_status_code(report.status))
+                .expect("status codes are validated when building the config");
+            reply::with_status(reply::json(&report), status).into_response()
+        }
+        Endpoint::Env => encoded_json(&actuator.env(), &headers),
+        Endpoint::Metrics => match actuator.metrics_async().await {
+            Ok(metrics) => reply::json(&metrics).into_response(),
+            Err(_) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        },
+        Endpoint::Shutdown => {
+            let header = headers
+                .get(SHUTDOWN_TOKEN_HEADER)
+                .and_then(|value| value.to_str().ok());
+            if !actuator.confirms_shutdown(shutdown_token(header, query.as_deref()).as_deref()) {
+                return StatusCode::FORBIDDEN.into_response();
+            }
+            spawn_shutdown(actuator);
+            StatusCode::ACCEPTED.into_response()
+        }
+        Endpoint::ThreadDump => encoded_json(&actuator.thread_dump_async().await, &headers),
+    }
+}
+
+/// Answers with the value as JSON, encoded as the request accepts.
+fn encoded_json(value: &impl Serialize, headers: &HeaderMap) -> Response {
+    let accept_encoding = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok());
+    let Ok(body) = json_body(value, accept_encoding) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mut response =
+        reply::with_header(body.bytes, header::CONTENT_TYPE, "application/json").into_response();
+    if let Some(encoding) = body.encoding {
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        headers.insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::FutureExt;
+    use futures::future::BoxFuture;
+
+    use crate::{
+        ActuatorError, BasicAuth, Config, HealthCheckFn, HealthChecker, HealthConfig, HealthGroup,
+        HealthStatus,
+    };
+
+    use super::*;
+
+    fn pass() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Ok(()) }.boxed()
+    }
+
+    fn fail() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Err(ActuatorError::new("connection refused")) }.boxed()
+    }
+
+    fn actuator(func: HealthCheckFn<ActuatorError>) -> Actuator {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Ping, Endpoint::Health, Endpoint::Env])
+            .basic_auth(Endpoint::Env, BasicAuth::new("admin", "s3cret"))
+            .health(
+                HealthConfig::default().checker(
+                    HealthChecker::new("db", crate::health_check_fn(func))
+                        .group(HealthGroup::Liveness)
+                        .group(HealthGroup::Readiness),
+                ),
+            )
+            .build()
+            .unwrap();
+        Actuator::new(cfg)
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let response = warp::test::request()
+            .path("/actuator/ping")
+            .reply(&actuator(pass).warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "pong");
+    }
+
+    #[tokio::test]
+    async fn test_health_up() {
+        let response = warp::test::request()
+            .path("/actuator/health")
+            .reply(&actuator(pass).warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(json["status"], "UP");
+        assert_eq!(json["checks"]["db"]["success"], true);
+    }
+
+    #[tokio::test]
+    async fn test_health_down() {
+        let response = warp::test::request()
+            .path("/actuator/health")
+            .reply(&actuator(fail).warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(json["checks"]["db"]["error"], "connection refused");
+    }
+
+    #[tokio::test]
+    async fn test_health_status_code() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Health])
+            .health_status_code(HealthStatus::Degraded, 503)
+            .health(HealthConfig::default().checker(
+                HealthChecker::new("cache", crate::health_check_fn(fail)).mandatory(false),
+            ))
+            .build()
+            .unwrap();
+        let response = warp::test::request()
+            .path("/actuator/health")
+            .reply(&Actuator::new(cfg).warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(json["status"], "DEGRADED");
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Metrics])
+            .build()
+            .unwrap();
+        let response = warp::test::request()
+            .path("/actuator/metrics")
+            .reply(&Actuator::new(cfg).warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(json["total_memory"].as_u64() > Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_info_not_modified() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Info])
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let filter = actuator.warp_filter();
+        let etag = actuator.info_etag();
+        let response = warp::test::request()
+            .path("/actuator/info")
+            .header(header::IF_NONE_MATCH, &etag)
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+        assert!(response.body().is_empty());
+        let response = warp::test::request()
+            .path("/actuator/info")
+            .header(header::IF_NONE_MATCH, r#"W/"0""#)
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        let filter = actuator(pass).warp_filter();
+        let response = warp::test::request()
+            .path("/actuator/env")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = warp::test::request()
+            .path("/actuator/env")
+            .header(header::AUTHORIZATION, "Basic YWRtaW46czNjcmV0")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_token() {
+        let exits = Arc::new(AtomicUsize::new(0));
+        let counter = exits.clone();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .shutdown_token("s3cret")
+            .with_exit_handler(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+        let filter = Actuator::new(cfg).warp_filter();
+        let response = warp::test::request()
+            .method("POST")
+            .path("/actuator/shutdown")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/actuator/shutdown?token=wrong")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(exits.load(Ordering::SeqCst), 0);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/actuator/shutdown?token=s3cret")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/actuator/shutdown")
+            .header(SHUTDOWN_TOKEN_HEADER, "s3cret")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while exits.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(exits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_answers_before_the_hooks() {
+        let (exit, exited) = std::sync::mpsc::channel();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Shutdown])
+            .with_exit_handler(move |code| exit.send(code).unwrap())
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        actuator.on_shutdown(Box::new(move || {
+            let _ = released.recv();
+        }));
+        let filter = actuator.warp_filter();
+        let request = warp::test::request()
+            .method("POST")
+            .path("/actuator/shutdown")
+            .reply(&filter);
+        let response = tokio::time::timeout(Duration::from_secs(5), request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert!(exited.try_recv().is_err());
+        drop(release);
+        assert_eq!(exited.recv_timeout(Duration::from_secs(5)), Ok(0));
+    }
+
+    #[tokio::test]
+    async fn test_index() {
+        let response = warp::test::request()
+            .path("/actuator")
+            .reply(&actuator(pass).warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"ping": "/ping", "health": "/health", "env": "/env"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_endpoint() {
+        let actuator = actuator(pass);
+        actuator
+            .register_endpoint(
+                "version-detail".to_string(),
+                Box::new(|| "1.0.0 (8f3c2a1)".to_string()),
+            )
+            .unwrap();
+        let response = warp::test::request()
+            .path("/actuator/version-detail")
+            .reply(&actuator.warp_filter())
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "1.0.0 (8f3c2a1)");
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn test_gzip() {
+        use std::io::Read;
+
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Env])
+            .env_prefix("CARGO_PKG_")
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let response = warp::test::request()
+            .path("/actuator/env")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .reply(&actuator.warp_filter())
+            .await;
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let body = response.body();
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut json)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::to_value(actuator.env()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_endpoint() {
+        let filter = actuator(pass).warp_filter();
+        for path in ["/actuator/info", "/actuator/metrics", "/health"] {
+            let response = warp::test::request().path(path).reply(&filter).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        }
+        let response = warp::test::request()
+            .method("POST")
+            .path("/actuator/ping")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
@@ -1,14 +1,1712 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+//! Health check and production-ready features for Rust services, in the
+//! spirit of Spring Boot's actuator.
+
+mod auth;
+mod config;
+mod duration;
+mod env;
+mod envs;
+mod error;
+mod health;
+mod info;
+mod integration;
+mod metrics;
+mod sampler;
+#[cfg(feature = "schema")]
+mod schema;
+mod snapshot;
+mod thread_dump;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use auth::constant_time_eq;
+use health::InnerHealth;
+use metrics::{CpuAverage, MetricsSource, SharedMetricsSource};
+use sampler::MetricsSampler;
+
+pub use auth::BasicAuth;
+pub use config::{Config, ConfigBuilder, Endpoint, ShutdownStrategy};
+pub use duration::parse_duration;
+pub use envs::{EnvSource, EnvVar};
+pub use error::ActuatorError;
+#[cfg(feature = "http-check")]
+pub use health::HttpHealthCheck;
+pub use health::{
+    DiskSpaceHealthCheck, ExecutionMode, HealthCheck, HealthCheckFn, HealthChecker, HealthConfig,
+    HealthDetails, HealthGroup, HealthInfo, HealthReport, HealthStatus, MemoryHealthCheck,
+    OptionalFailurePolicy, TcpHealthCheck, health_check_fn,
+};
+pub use info::{ApplicationInfo, GITInfo, Info, RuntimeInfo};
+#[cfg(feature = "hyper")]
+pub use integration::ActuatorService;
+pub use metrics::{ByteUnit, DiskMetric, MemoryField, Metrics, SystemSource};
+pub use snapshot::Snapshot;
+pub use thread_dump::{ThreadFrame, ThreadStack};
+
+/// Entry point exposing the actuator endpoints of a service.
+///
+/// `E` is the error type returned by the registered health checks.
+pub struct Actuator<E = ActuatorError> {
+    inner: Arc<Inner<E>>,
+}
+
+impl<E> Clone for Actuator<E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Callback run when the actuator shuts the process down.
+type ShutdownHook = Box<dyn FnOnce() + Send>;
+
+/// Handler of an endpoint registered through [`Actuator::register_endpoint`],
+/// returning the response body.
+pub type EndpointHandler = Box<dyn Fn() -> String + Send + Sync>;
+
+struct Inner<E> {
+    cfg: Arc<Config<E>>,
+    /// Replaced whenever a contributor is added, served snapshots stay valid.
+    info: RwLock<Arc<Info>>,
+    envs: Arc<BTreeMap<String, String>>,
+    detailed_envs: Arc<BTreeMap<String, EnvVar>>,
+    health: InnerHealth<E>,
+    shutdown_hooks: Mutex<Vec<ShutdownHook>>,
+    /// Handlers of the registered endpoints, keyed by path.
+    custom_endpoints: RwLock<BTreeMap<String, Arc<EndpointHandler>>>,
+    metrics_sampler: Mutex<Option<MetricsSampler>>,
+    metrics_source: SharedMetricsSource,
+    /// Set when the CPU usage is averaged over a window.
+    cpu_average: Option<Mutex<CpuAverage>>,
+    #[cfg(feature = "actix")]
+    actix_server: std::sync::OnceLock<actix_web::dev::ServerHandle>,
+}
+
+#[cfg(feature = "schema")]
+impl Actuator {
+    /// Returns the JSON schemas of the responses of the info, health and
+    /// metrics endpoints as they are serialized, keyed by the endpoint name.
+    pub fn response_schemas() -> BTreeMap<String, schemars::Schema> {
+        let settings = schemars::generate::SchemaSettings::default().for_serialize();
+        BTreeMap::from([
+            (
+                "info".to_string(),
+                settings
+                    .clone()
+                    .into_generator()
+                    .into_root_schema_for::<Info>(),
+            ),
+            (
+                "health".to_string(),
+                settings
+                    .clone()
+                    .into_generator()
+                    .into_root_schema_for::<HealthReport>(),
+            ),
+            (
+                "metrics".to_string(),
+                settings.into_generator().into_root_schema_for::<Metrics>(),
+            ),
+        ])
+    }
+}
+
+impl<E: Error + Send + 'static> Actuator<E> {
+    /// Creates an actuator from the given configuration.
+    pub fn new(mut cfg: Config<E>) -> Self {
+        let info = Info::new(&cfg);
+        // The checks can change at runtime, from here on they are owned by the
+        // health state.
+        let health = InnerHealth::new(std::mem::take(&mut cfg.health));
+        let detailed_envs = envs::collect_detailed(
+            env::vars(),
+            cfg.dotenv.clone(),
+            cfg.dotenv_override,
+            cfg.env_prefix.as_deref(),
+            &cfg.env_mask,
+        );
+        let envs = detailed_envs
+            .iter()
+            .map(|(key, var)| (key.clone(), var.value.clone()))
+            .collect();
+        let cpu_average = cfg
+            .cpu_usage_window
+            .map(|window| Mutex::new(CpuAverage::new(window)));
+        let system_source = cfg
+            .system_source
+            .take()
+            .unwrap_or_else(|| Box::new(sysinfo::System::new()));
+        Self {
+            inner: Arc::new(Inner {
+                cfg: Arc::new(cfg),
+                info: RwLock::new(Arc::new(info)),
+                envs: Arc::new(envs),
+                detailed_envs: Arc::new(detailed_envs),
+                health,
+                shutdown_hooks: Mutex::new(Vec::new()),
+                custom_endpoints: RwLock::new(BTreeMap::new()),
+                metrics_sampler: Mutex::new(None),
+                metrics_source: Arc::new(Mutex::new(MetricsSource::new(system_source))),
+                cpu_average,
+                #[cfg(feature = "actix")]
+                actix_server: std::sync::OnceLock::new(),
+            }),
+        }
+    }
+
+    /// Reports whether the endpoint is enabled in the configuration.
+    pub fn is_enabled(&self, ep: &Endpoint) -> bool {
+        self.inner.cfg.endpoints.contains(ep)
+    }
+
+    /// Returns the endpoints enabled in the configuration.
+    pub fn enabled_endpoints(&self) -> Vec<Endpoint> {
+        self.inner.cfg.endpoints.clone()
+    }
+
+    /// Returns the enabled and the registered endpoints, each name mapped to the
+    /// path it is served on relative to the base path. The framework
+    /// integrations serve it on the base path itself.
+    pub fn index(&self) -> HashMap<String, String> {
+        let custom = self.inner.custom_endpoints.read().unwrap();
+        let built_in = self
+            .inner
+            .cfg
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.path());
+        built_in
+            .chain(custom.keys().map(String::as_str))
+            .map(|path| (path[1..].to_string(), path.to_string()))
+            .collect()
+    }
+
+    /// Adds an endpoint served on `/<name>` under the base path alongside the
+    /// built-in ones, answering GET requests with the text the handler returns.
+    /// Replaces the one registered under the same name if any.
+    ///
+    /// Fails when the name is not made of ASCII letters, digits, `-`, `_` and
+    /// `.` only, so that every framework routes it literally, when it is a
+    /// relative path segment such as `..` or when it is the one of a built-in
+    /// endpoint. The framework integrations serve the endpoints registered by
+    /// the time they are built.
+    pub fn register_endpoint(
+        &self,
+        name: String,
+        handler: EndpointHandler,
+    ) -> Result<(), ActuatorError> {
+        let literal = name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.'));
+        if !literal || matches!(name.as_str(), "" | "." | "..") {
+            return Err(ActuatorError::new(format!(
+                "endpoint name {name:?} must be a path segment of ASCII letters, digits, \
+                 '-', '_' and '.'"
+            )));
+        }
+        let path = format!("/{name}");
+        if Endpoint::ALL.iter().any(|endpoint| endpoint.path() == path) {
+            return Err(ActuatorError::new(format!(
+                "endpoint name {name:?} is taken by a built-in endpoint"
+            )));
+        }
+        self.inner
+            .custom_endpoints
+            .write()
+            .unwrap()
+            .insert(path, Arc::new(handler));
+        Ok(())
+    }
+
+    /// Returns the registered endpoints as paths relative to the base path, with
+    /// their handlers.
+    #[cfg(any(
+        test,
+        feature = "actix",
+        feature = "axum",
+        feature = "hyper",
+        feature = "warp"
+    ))]
+    pub(crate) fn custom_endpoints(&self) -> Vec<(String, Arc<EndpointHandler>)> {
+        let endpoints = self.inner.custom_endpoints.read().unwrap();
+        endpoints
+            .iter()
+            .map(|(path, handler)| (path.clone(), handler.clone()))
+            .collect()
+    }
+
+    /// Reports whether a request to the endpoint carrying the `Authorization`
+    /// header, if any, may be served. Endpoints without credentials always are.
+    pub fn authorizes(&self, endpoint: Endpoint, authorization: Option<&str>) -> bool {
+        self.inner
+            .cfg
+            .basic_auth
+            .get(&endpoint)
+            .is_none_or(|credentials| credentials.verify(authorization))
+    }
+
+    /// Reports whether a shutdown request carrying the confirmation token, if
+    /// any, may proceed. Without a configured token it always may.
+    pub fn confirms_shutdown(&self, token: Option<&str>) -> bool {
+        self.inner
+            .cfg
+            .shutdown_token
+            .as_deref()
+            .is_none_or(|expected| token.is_some_and(|token| constant_time_eq(token, expected)))
+    }
+
+    /// Reports that the service is reachable.
+    pub fn ping(&self) -> bool {
+        true
+    }
+
+    /// Reports that the process is responsive without evaluating anything, not
+    /// even cached checks, for high-frequency liveness probes. Unlike
+    /// [`Actuator::liveness`], which runs the checks tagged with
+    /// [`HealthGroup::Liveness`], it cannot fail because of a dependency.
+    pub fn alive(&self) -> bool {
+        true
+    }
+
+    /// Returns the configured payload the ping endpoint answers with.
+    pub fn ping_response(&self) -> String {
+        self.inner.cfg.ping_response.clone()
+    }
+
+    /// Returns the application, git and runtime information, followed by the
+    /// contributed blocks.
+    pub fn info(&self) -> Arc<Info> {
+        self.inner.info.read().unwrap().clone()
+    }
+
+    /// Adds a block published by the info endpoint under the key, replacing the
+    /// one contributed under the same key if any.
+    ///
+    /// Fails for the keys of the built-in sections: `application`, `git` and
+    /// `runtime`.
+    #[cfg(feature = "serde")]
+    pub fn add_info_contributor(
+        &self,
+        key: String,
+        value: serde_json::Value,
+    ) -> Result<(), ActuatorError> {
+        let mut info = self.inner.info.write().unwrap();
+        let mut updated = Info::clone(&info);
+        updated.contribute(key, value)?;
+        *info = Arc::new(updated);
+        Ok(())
+    }
+
+    /// Returns a weak ETag of the info, changing along its content but not along
+    /// the uptime it reports. The framework integrations answer the info
+    /// requests carrying it in `If-None-Match` with 304.
+    #[cfg(feature = "serde")]
+    pub fn info_etag(&self) -> String {
+        self.inner.info.read().unwrap().etag()
+    }
+
+    /// Returns the application and runtime details as OpenTelemetry resource
+    /// attributes, such as `service.name`, `service.version` and
+    /// `deployment.environment`. Attributes without a value are left out.
+    pub fn otel_resource_attributes(&self) -> Vec<(String, String)> {
+        self.inner.info.read().unwrap().otel_resource_attributes()
+    }
+
+    /// Returns how long the service has been running, zero if the clock moved back
+    /// since it started.
+    pub fn uptime(&self) -> Duration {
+        self.inner.info.read().unwrap().application.uptime()
+    }
+
+    /// Runs the configured health checks, or serves the last successful run while
+    /// it is younger than the configured cache duration.
+    ///
+    /// The report carries the outcome of each check, the aggregate
+    /// [`HealthStatus`] and when the checks were run. Must be awaited within a
+    /// Tokio runtime, the check timeouts rely on its timer.
+    pub async fn health(&self) -> HealthReport {
+        self.inner.health.get(None).await
+    }
+
+    /// Returns the HTTP status code the framework integrations answer the health
+    /// endpoint with for the status, the one set through
+    /// [`ConfigBuilder::health_status_code`] if any, else 200 for up and
+    /// degraded and 503 for down.
+    pub fn health_status_code(&self, status: HealthStatus) -> u16 {
+        match self.inner.cfg.health_status_codes.get(&status) {
+            Some(code) => *code,
+            None if status == HealthStatus::Down => 503,
+            None => 200,
+        }
+    }
+
+    /// Runs [`Actuator::health`] and returns whether each check succeeded, keyed
+    /// by the checker key and sorted by it.
+    pub async fn health_summary(&self) -> Vec<(String, bool)> {
+        let mut summary: Vec<_> = self
+            .health()
+            .await
+            .checks()
+            .values()
+            .map(|info| (info.key().to_string(), info.success()))
+            .collect();
+        summary.sort();
+        summary
+    }
+
+    /// Runs [`Actuator::health`] and renders the report in the shape of the
+    /// Spring Boot health endpoint, `{"status":"UP","components":{"db":{"status":"UP"}}}`,
+    /// for dashboards built against it. Degraded is reported as `OUT_OF_SERVICE`.
+    #[cfg(feature = "spring-compat")]
+    pub async fn health_spring(&self) -> serde_json::Value {
+        self.health().await.to_spring()
+    }
+
+    /// Runs [`Actuator::health`] to completion on a runtime of its own, for
+    /// callers outside of an async context.
+    ///
+    /// Fails when the runtime cannot be created. Must not be called from within
+    /// an async runtime, which panics.
+    #[cfg(feature = "blocking")]
+    pub fn health_blocking(&self) -> Result<HealthReport, ActuatorError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        Ok(runtime.block_on(self.health()))
+    }
+
+    /// Runs the configured health checks even while a cached run is still fresh,
+    /// and caches the outcome like [`Actuator::health`] does. Meant for on-demand
+    /// diagnostics.
+    pub async fn health_fresh(&self) -> HealthReport {
+        self.inner.health.get_fresh(None).await
+    }
+
+    /// Runs the health checks tagged with [`HealthGroup::Liveness`], cached like
+    /// [`Actuator::health`]. Reports up without running any until the
+    /// [`startup_grace`](HealthConfig::startup_grace) has elapsed since startup.
+    pub async fn liveness(&self) -> HealthReport {
+        self.inner.health.get_liveness(self.uptime()).await
+    }
+
+    /// Runs the health checks tagged with [`HealthGroup::Readiness`], cached like
+    /// [`Actuator::health`].
+    pub async fn readiness(&self) -> HealthReport {
+        self.inner
+            .health
+            .get(Some(HealthGroup::Readiness.name()))
+            .await
+    }
+
+    /// Runs the health checks tagged with the group, such as `startup` or
+    /// `external`, cached like [`Actuator::health`]. Without any check in the
+    /// group the status is up, unless the health config is strict.
+    pub async fn health_group(&self, name: &str) -> HealthReport {
+        self.inner.health.get(Some(name)).await
+    }
+
+    /// Returns the key of each health check, and whether it is mandatory,
+    /// without running any. They come in the order they were configured or
+    /// registered, a replaced check last.
+    pub fn health_check_keys(&self) -> Vec<(String, bool)> {
+        self.inner.health.keys()
+    }
+
+    /// Adds a health check, replacing the one registered under the same key if
+    /// any. Cached evaluations are discarded.
+    pub fn register_health_check(&self, checker: HealthChecker<E>) {
+        self.inner.health.register(checker);
+    }
+
+    /// Removes the health check registered under the key, reporting whether there
+    /// was one. Cached evaluations are discarded.
+    pub fn deregister_health_check(&self, key: &str) -> bool {
+        self.inner.health.deregister(key)
+    }
+
+    /// Marks the service as ready to accept traffic or not, ready by default.
+    /// While not ready, [`Actuator::readiness`] and [`Actuator::health`] report
+    /// [`HealthStatus::Down`] whatever the checks say, [`Actuator::liveness`] is
+    /// left alone. Meant to drain traffic, e.g. ahead of a shutdown.
+    pub fn set_ready(&self, ready: bool) {
+        self.inner.health.set_ready(ready);
+    }
+
+    /// Returns the environment variables of the process, captured at startup,
+    /// along with the ones of the configured `.env` file if any, limited to the
+    /// configured prefix if any and sorted by name.
+    ///
+    /// Values of the variables matching the configured mask patterns are
+    /// replaced with `******`.
+    pub fn env(&self) -> Arc<BTreeMap<String, String>> {
+        self.inner.envs.clone()
+    }
+
+    /// Returns the same variables as [`Actuator::env`], each along with where
+    /// it was read from.
+    pub fn env_detailed(&self) -> Arc<BTreeMap<String, EnvVar>> {
+        self.inner.detailed_envs.clone()
+    }
+
+    /// Returns the current memory and CPU usage of the host and of the process,
+    /// and the space of the disks limited to the configured mount point if any.
+    /// The global CPU usage is averaged over the configured
+    /// [`cpu_usage_window`](ConfigBuilder::cpu_usage_window) if any.
+    ///
+    /// Fails when the system information is unavailable, e.g. in a sandbox.
+    pub fn metrics(&self) -> Result<Metrics, ActuatorError> {
+        let mut metrics = {
+            let mut source = self
+                .inner
+                .metrics_source
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            source.collect(self.inner.cfg.disk_mount_point.as_deref())?
+        };
+        if let Some(cpu_average) = &self.inner.cpu_average {
+            metrics.global_cpu_usage = cpu_average
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .sample();
+        }
+        Ok(metrics)
+    }
+
+    /// Collects the metrics like [`Actuator::metrics`] does, on the blocking
+    /// thread pool of the Tokio runtime so that the system calls do not stall
+    /// the executor.
+    ///
+    /// Must be awaited within a Tokio runtime. Panics when collecting the
+    /// metrics panics.
+    pub async fn metrics_async(&self) -> Result<Metrics, ActuatorError> {
+        let actuator = self.clone();
+        match tokio::task::spawn_blocking(move || actuator.metrics()).await {
+            Ok(metrics) => metrics,
+            Err(err) => panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    /// Samples the metrics every interval on a background thread, keeping the
+    /// last [`metrics_history_size`](ConfigBuilder::metrics_history_size)
+    /// samples for [`Actuator::metrics_history`]. Replaces the sampler started
+    /// before if any, which stops like the last one does when the actuator is
+    /// dropped.
+    ///
+    /// Fails when the thread cannot be spawned.
+    pub fn start_metrics_sampler(&self, interval: Duration) -> Result<(), ActuatorError> {
+        let cfg = &self.inner.cfg;
+        let sampler = MetricsSampler::start(
+            interval,
+            cfg.metrics_history_size,
+            self.inner.metrics_source.clone(),
+            cfg.disk_mount_point.clone(),
+        )?;
+        *self.inner.metrics_sampler.lock().unwrap() = Some(sampler);
+        Ok(())
+    }
+
+    /// Returns the samples of the metrics sampler, the oldest first, none unless
+    /// it was started.
+    pub fn metrics_history(&self) -> Vec<Metrics> {
+        self.inner
+            .metrics_sampler
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or_else(Vec::new, MetricsSampler::history)
+    }
+
+    /// Gathers the info, the health report, the env and the current metrics in a
+    /// single [`Snapshot`], leaving out the sections of the disabled endpoints,
+    /// and the metrics when unavailable.
+    /// The health checks are run like [`Actuator::health`] does.
+    pub async fn snapshot(&self) -> Snapshot {
+        let health = match self.is_enabled(&Endpoint::Health) {
+            true => Some(self.health().await),
+            false => None,
+        };
+        Snapshot {
+            info: self.is_enabled(&Endpoint::Info).then(|| self.info()),
+            health,
+            env: self.is_enabled(&Endpoint::Env).then(|| self.env()),
+            metrics: self
+                .is_enabled(&Endpoint::Metrics)
+                .then(|| self.metrics().ok())
+                .flatten(),
+        }
+    }
+
+    /// Returns the current memory and CPU usage of the host and of the process in
+    /// the Prometheus text exposition format, labelled with the application name
+    /// and env. Fails like [`Actuator::metrics`] does.
+    #[cfg(feature = "prometheus")]
+    pub fn metrics_prometheus(&self) -> Result<String, ActuatorError> {
+        let cfg = &self.inner.cfg;
+        Ok(self
+            .metrics()?
+            .prometheus(&[("name", &cfg.name), ("env", &cfg.env)]))
+    }
+
+    /// Registers a callback run on shutdown, the callbacks run in the reverse
+    /// order of their registration.
+    pub fn on_shutdown(&self, hook: Box<dyn FnOnce() + Send>) {
+        self.inner.shutdown_hooks.lock().unwrap().push(hook);
+    }
+
+    /// Runs the shutdown hooks and ends the process as the configured
+    /// [`ShutdownStrategy`] says, with the code of [`ShutdownStrategy::Exit`] or
+    /// else 0.
+    pub fn shutdown(&self) {
+        let code = match self.inner.cfg.shutdown_strategy {
+            ShutdownStrategy::Exit(code) => code,
+            ShutdownStrategy::Signal | ShutdownStrategy::Callback(_) => 0,
+        };
+        self.shutdown_with_code(code)
+    }
+
+    /// Runs the shutdown hooks and ends the process with the exit code as the
+    /// configured [`ShutdownStrategy`] says.
+    ///
+    /// The process is ended once the hooks finished or the configured shutdown
+    /// timeout elapsed, whichever comes first. Only returns if the handler
+    /// configured through [`ConfigBuilder::with_exit_handler`] does, or with the
+    /// strategies not exiting right away. A closed callback channel is ignored.
+    pub fn shutdown_with_code(&self, code: i32) {
+        self.run_shutdown_hooks();
+        match &self.inner.cfg.shutdown_strategy {
+            ShutdownStrategy::Exit(_) => (self.inner.cfg.exit_handler)(code),
+            #[cfg(unix)]
+            ShutdownStrategy::Signal => {
+                // SAFETY: signals the current process, which handles it as it
+                // handles any other termination request.
+                unsafe { libc::kill(libc::getpid(), libc::SIGTERM) };
+            }
+            #[cfg(not(unix))]
+            ShutdownStrategy::Signal => (self.inner.cfg.exit_handler)(code),
+            ShutdownStrategy::Callback(shutdown) => {
+                let _ = shutdown.send(code);
+            }
+        }
+    }
+
+    /// Runs the registered hooks in reverse order on a separate thread, waiting
+    /// for them up to the configured shutdown timeout. A panicking hook does not
+    /// prevent the next ones from running.
+    pub(crate) fn run_shutdown_hooks(&self) {
+        let hooks = std::mem::take(&mut *self.inner.shutdown_hooks.lock().unwrap());
+        if hooks.is_empty() {
+            return;
+        }
+        let (done, finished) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("actuator-shutdown".to_string())
+            .spawn(move || {
+                for hook in hooks.into_iter().rev() {
+                    let _ = panic::catch_unwind(AssertUnwindSafe(hook));
+                }
+                let _ = done.send(());
+            });
+        if spawned.is_ok() {
+            let _ = finished.recv_timeout(self.inner.cfg.shutdown_timeout);
+        }
+    }
+
+    /// Returns the resolved stacks of the threads of the process, the calling
+    /// one first.
+    ///
+    /// Other threads are only captured on Linux, on a best effort basis: a thread
+    /// blocking the signal used to interrupt it is reported without frames. The
+    /// signal handler is installed on the first dump and kept afterwards.
+    ///
+    /// Unwinding from a signal handler is not async-signal-safe, a thread
+    /// interrupted while holding the lock of the dynamic loader can deadlock.
+    /// The dump also waits up to 100ms on each thread not answering, see
+    /// [`Actuator::thread_dump_async`] to keep it off the executor.
+    pub fn thread_dump(&self) -> Vec<ThreadStack> {
+        thread_dump::capture_all()
+    }
+
+    /// Captures the stacks like [`Actuator::thread_dump`] does, on the blocking
+    /// thread pool of the Tokio runtime.
+    ///
+    /// Must be awaited within a Tokio runtime.
+    pub async fn thread_dump_async(&self) -> Vec<ThreadStack> {
+        match tokio::task::spawn_blocking(thread_dump::capture_all).await {
+            Ok(stacks) => stacks,
+            Err(err) => panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    /// Returns the stacks of [`Actuator::thread_dump`] as readable text, each
+    /// under a header naming the thread.
+    pub fn thread_dump_string(&self) -> String {
+        thread_dump::render(&thread_dump::capture_all())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    use futures::FutureExt;
+    use futures::future::BoxFuture;
+
     use super::*;
 
+    fn pass() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Ok(()) }.boxed()
+    }
+
+    fn fail() -> BoxFuture<'static, Result<(), ActuatorError>> {
+        async { Err(ActuatorError::new("timed out")) }.boxed()
+    }
+
+    /// Check answering as `func` does, counting the calls.
+    fn counting(
+        calls: &Arc<AtomicUsize>,
+        func: HealthCheckFn<ActuatorError>,
+    ) -> HealthCheck<ActuatorError> {
+        let (calls, func) = (calls.clone(), health_check_fn(func));
+        Arc::new(move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            func()
+        })
+    }
+
+    fn actuator(checkers: Vec<HealthChecker>) -> Actuator {
+        Actuator::new(Config {
+            name: "orders".to_string(),
+            env: "test".to_string(),
+            version: "1.0.0".to_string(),
+            package_version: "1.0.0".to_string(),
+            port: 8080,
+            endpoints: vec![Endpoint::Ping, Endpoint::Health],
+            base_path: "/actuator".to_string(),
+            basic_auth: HashMap::new(),
+            ping_response: "pong".to_string(),
+            env_prefix: None,
+            env_mask: vec!["password".to_string()],
+            dotenv_path: None,
+            dotenv_override: false,
+            dotenv: Vec::new(),
+            disk_mount_point: None,
+            metrics_history_size: 60,
+            cpu_usage_window: None,
+            system_source: None,
+            health_status_codes: HashMap::new(),
+            shutdown_timeout: Duration::from_secs(10),
+            shutdown_token: None,
+            shutdown_strategy: ShutdownStrategy::Exit(0),
+            exit_handler: Arc::new(|_| {}),
+            health: HealthConfig {
+                checkers,
+                ..Default::default()
+            },
+        })
+    }
+
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Actuator>();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let actuator = actuator(vec![
+            HealthChecker::new("db", health_check_fn(pass))
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+        ]);
+        let snapshot = actuator.snapshot().await;
+        assert_eq!(snapshot.health().unwrap().status(), HealthStatus::Up);
+        assert!(snapshot.health().unwrap().checks().contains_key("db"));
+        assert!(snapshot.info().is_none());
+        assert!(snapshot.env().is_none());
+        assert!(snapshot.metrics().is_none());
+
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .endpoints([Endpoint::Info, Endpoint::Env, Endpoint::Metrics])
+            .env_prefix("CARGO_PKG_")
+            .build()
+            .unwrap();
+        let snapshot = Actuator::new(cfg).snapshot().await;
+        assert!(snapshot.health().is_none());
+        assert_eq!(snapshot.info().unwrap().application.name, "orders");
+        assert_eq!(snapshot.env().unwrap()["CARGO_PKG_NAME"], "rust-actuator");
+        assert_eq!(snapshot.metrics().unwrap().pid, std::process::id());
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_serialize_snapshot() {
+        let json = serde_json::to_value(actuator(vec![]).snapshot().await).unwrap();
+        let keys: Vec<_> = json.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["health"]);
+        assert_eq!(json["health"]["status"], "UP");
+    }
+
+    #[test]
+    fn test_is_enabled() {
+        let actuator = actuator(vec![]);
+        assert!(actuator.is_enabled(&Endpoint::Ping));
+        assert!(actuator.is_enabled(&Endpoint::Health));
+        assert!(!actuator.is_enabled(&Endpoint::Metrics));
+        assert!(!actuator.is_enabled(&Endpoint::Shutdown));
+        assert_eq!(
+            actuator.enabled_endpoints(),
+            [Endpoint::Ping, Endpoint::Health]
+        );
+    }
+
+    #[test]
+    fn test_ping() {
+        assert!(actuator(vec![]).ping());
+    }
+
+    #[tokio::test]
+    async fn test_alive_runs_no_checks() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let actuator = actuator(vec![
+            HealthChecker::new("db", counting(&calls, fail)).group(HealthGroup::Liveness),
+        ]);
+        assert!(actuator.alive());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(actuator.liveness().await.status(), HealthStatus::Down);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(actuator.alive());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_authorizes() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .basic_auth(Endpoint::Env, BasicAuth::new("admin", "s3cret"))
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        assert!(actuator.authorizes(Endpoint::Env, Some("Basic YWRtaW46czNjcmV0")));
+        assert!(!actuator.authorizes(Endpoint::Env, Some("Basic YWRtaW46d3Jvbmc=")));
+        assert!(!actuator.authorizes(Endpoint::Env, None));
+        assert!(actuator.authorizes(Endpoint::Info, None));
+    }
+
+    #[test]
+    fn test_confirms_shutdown() {
+        assert!(actuator(vec![]).confirms_shutdown(None));
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .shutdown_token("s3cret")
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        assert!(actuator.confirms_shutdown(Some("s3cret")));
+        assert!(!actuator.confirms_shutdown(Some("s3cre")));
+        assert!(!actuator.confirms_shutdown(Some("")));
+        assert!(!actuator.confirms_shutdown(None));
+    }
+
+    #[test]
+    fn test_otel_resource_attributes() {
+        let attributes = actuator(vec![]).otel_resource_attributes();
+        assert!(attributes.contains(&("service.name".to_string(), "orders".to_string())));
+    }
+
+    #[test]
+    fn test_register_endpoint() {
+        let actuator = actuator(vec![]);
+        actuator
+            .register_endpoint("features".to_string(), Box::new(|| "beta".to_string()))
+            .unwrap();
+        actuator
+            .register_endpoint("caches".to_string(), Box::new(|| "orders".to_string()))
+            .unwrap();
+        actuator
+            .register_endpoint("features".to_string(), Box::new(|| "stable".to_string()))
+            .unwrap();
+        let endpoints: Vec<_> = actuator
+            .custom_endpoints()
+            .into_iter()
+            .map(|(path, handler)| (path, handler()))
+            .collect();
+        assert_eq!(
+            endpoints,
+            [
+                ("/caches".to_string(), "orders".to_string()),
+                ("/features".to_string(), "stable".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index() {
+        let actuator = actuator(vec![]);
+        let expected = |entries: &[&str]| -> HashMap<String, String> {
+            entries
+                .iter()
+                .map(|name| (name.to_string(), format!("/{name}")))
+                .collect()
+        };
+        assert_eq!(actuator.index(), expected(&["ping", "health"]));
+        actuator
+            .register_endpoint("caches".to_string(), Box::new(String::new))
+            .unwrap();
+        assert_eq!(actuator.index(), expected(&["ping", "health", "caches"]));
+
+        let cfg = Config::builder().name("orders").port(8080).build().unwrap();
+        assert!(Actuator::new(cfg).index().is_empty());
+    }
+
+    #[test]
+    fn test_register_endpoint_invalid_name() {
+        let actuator = actuator(vec![]);
+        for (name, err) in [
+            (
+                "health",
+                r#"endpoint name "health" is taken by a built-in endpoint"#,
+            ),
+            (
+                "threaddump",
+                r#"endpoint name "threaddump" is taken by a built-in endpoint"#,
+            ),
+        ] {
+            let result = actuator.register_endpoint(name.to_string(), Box::new(String::new));
+            assert_eq!(result.err().unwrap().to_string(), err);
+        }
+        for name in [
+            "",
+            "caches/orders",
+            "{id}",
+            "*rest",
+            ":id",
+            "..",
+            "a b",
+            "caché",
+        ] {
+            let result = actuator.register_endpoint(name.to_string(), Box::new(String::new));
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                format!(
+                    "endpoint name {name:?} must be a path segment of ASCII letters, digits, \
+                     '-', '_' and '.'"
+                )
+            );
+        }
+        assert!(actuator.custom_endpoints().is_empty());
+        actuator
+            .register_endpoint("build-info_v1.2".to_string(), Box::new(String::new))
+            .unwrap();
+        assert_eq!(actuator.custom_endpoints().len(), 1);
+    }
+
+    #[test]
+    fn test_ping_response() {
+        let cfg = Config::builder().name("orders").port(8080).build().unwrap();
+        assert_eq!(Actuator::new(cfg).ping_response(), "pong");
+
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .ping_response(r#"{"status":"UP"}"#)
+            .build()
+            .unwrap();
+        assert_eq!(Actuator::new(cfg).ping_response(), r#"{"status":"UP"}"#);
+    }
+
+    #[test]
+    fn test_info() {
+        let info = actuator(vec![]).info();
+        assert_eq!(info.application.name, "orders");
+        assert_eq!(info.application.env, "test");
+        assert_eq!(info.application.version, "1.0.0");
+        assert_eq!(info.runtime.os, std::env::consts::OS);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_add_info_contributor() {
+        let actuator = actuator(vec![]);
+        let before = actuator.info();
+        actuator
+            .add_info_contributor("region".to_string(), serde_json::json!("eu-west-1"))
+            .unwrap();
+        let err = actuator
+            .add_info_contributor("git".to_string(), serde_json::json!("dirty"))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "info key \"git\" is reserved");
+
+        let json = serde_json::to_value(&*actuator.info()).unwrap();
+        assert_eq!(json["region"], "eu-west-1");
+        assert!(json["git"].is_object());
+        assert!(before.contributed.is_empty());
+    }
+
+    #[test]
+    fn test_uptime() {
+        let actuator = actuator(vec![]);
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(actuator.uptime() > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_env_masked() {
+        let _lock = env::ENV_LOCK.lock().unwrap();
+        env::set_var("ORDERS_DB_PASSWORD", "hunter2");
+        env::set_var("ORDERS_DB_HOST", "db.internal");
+        let envs = actuator(vec![]).env();
+        env::remove_var("ORDERS_DB_PASSWORD");
+        env::remove_var("ORDERS_DB_HOST");
+        assert_eq!(envs["ORDERS_DB_PASSWORD"], "******");
+        assert_eq!(envs["ORDERS_DB_HOST"], "db.internal");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_env_skips_non_unicode() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let _lock = env::ENV_LOCK.lock().unwrap();
+        env::set_var("ORDERS_BINARY", std::ffi::OsStr::from_bytes(b"\xff"));
+        env::set_var("ORDERS_DB_HOST", "db.internal");
+        let envs = actuator(vec![]).env();
+        env::remove_var("ORDERS_BINARY");
+        env::remove_var("ORDERS_DB_HOST");
+        assert!(!envs.contains_key("ORDERS_BINARY"));
+        assert_eq!(envs["ORDERS_DB_HOST"], "db.internal");
+    }
+
+    #[test]
+    fn test_env_detailed() {
+        let _lock = env::ENV_LOCK.lock().unwrap();
+        env::set_var("ORDERS_DB_PASSWORD", "hunter2");
+        let actuator = actuator(vec![]);
+        env::remove_var("ORDERS_DB_PASSWORD");
+        let envs = actuator.env_detailed();
+        assert_eq!(envs["ORDERS_DB_PASSWORD"].value(), "******");
+        assert_eq!(envs["ORDERS_DB_PASSWORD"].source(), EnvSource::Process);
+        assert_eq!(envs.len(), actuator.env().len());
+    }
+
+    #[test]
+    fn test_env_dotenv() {
+        let _lock = env::ENV_LOCK.lock().unwrap();
+        let path = std::env::temp_dir().join(format!("actuator-lib-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "ORDERS_QUEUE=orders\nORDERS_DB_PASSWORD=hunter2\nORDERS_DB_HOST=file.internal\n",
+        )
+        .unwrap();
+        env::set_var("ORDERS_DB_HOST", "db.internal");
+        let cfg = |dotenv_override| {
+            Config::builder()
+                .name("orders")
+                .port(8080)
+                .dotenv_path(&path)
+                .dotenv_override(dotenv_override)
+                .build()
+                .unwrap()
+        };
+        let actuator = Actuator::new(cfg(false));
+        let overridden = Actuator::new(cfg(true));
+        env::remove_var("ORDERS_DB_HOST");
+        std::fs::remove_file(&path).unwrap();
+
+        let envs = actuator.env();
+        assert_eq!(envs["ORDERS_QUEUE"], "orders");
+        assert_eq!(envs["ORDERS_DB_PASSWORD"], "******");
+        assert_eq!(envs["ORDERS_DB_HOST"], "db.internal");
+        assert_eq!(
+            actuator.env_detailed()["ORDERS_QUEUE"].source(),
+            EnvSource::DotEnv
+        );
+        assert_eq!(
+            actuator.env_detailed()["ORDERS_DB_HOST"].source(),
+            EnvSource::Process
+        );
+        assert_eq!(overridden.env()["ORDERS_DB_HOST"], "file.internal");
+        assert!(std::env::var("ORDERS_QUEUE").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_health_mixed_checkers() {
+        let actuator = actuator(vec![
+            HealthChecker::new("db", health_check_fn(pass))
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+            HealthChecker::new("search", health_check_fn(fail))
+                .mandatory(false)
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+        ]);
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = actuator.health().await;
+        assert_eq!(status, HealthStatus::Degraded);
+        assert!(data["db"].success);
+        assert_eq!(data["db"].error, None);
+        assert!(!data["search"].success);
+        assert_eq!(data["search"].error.as_deref(), Some("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_health_cached() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let health = HealthConfig::default()
+            .checker(
+                HealthChecker::new("db", counting(&calls, pass))
+                    .group(HealthGroup::Liveness)
+                    .group(HealthGroup::Readiness),
+            )
+            .cache_duration(Duration::from_secs(60));
+        let actuator = Actuator::new(
+            Config::builder()
+                .name("orders")
+                .port(8080)
+                .health(health)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(actuator.health().await.status, HealthStatus::Up);
+        assert_eq!(actuator.health().await.status, HealthStatus::Up);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(actuator.health_fresh().await.status, HealthStatus::Up);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "spring-compat")]
+    #[tokio::test]
+    async fn test_health_spring() {
+        let checker = |key: &str, is_mandatory, func| {
+            HealthChecker::new(key, health_check_fn(func)).mandatory(is_mandatory)
+        };
+        let actuator = actuator(vec![
+            checker("db", true, pass),
+            checker("cache", false, fail),
+        ]);
+        assert_eq!(
+            actuator.health_spring().await,
+            serde_json::json!({
+                "status": "OUT_OF_SERVICE",
+                "components": {
+                    "db": { "status": "UP" },
+                    "cache": { "status": "DOWN", "details": { "error": "timed out" } },
+                },
+            })
+        );
+
+        actuator.register_health_check(checker("disk", true, fail));
+        let health = actuator.health_spring().await;
+        assert_eq!(health["status"], "DOWN");
+        assert_eq!(health["components"]["disk"]["status"], "DOWN");
+    }
+
+    #[tokio::test]
+    async fn test_health_group() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let actuator = actuator(vec![
+            HealthChecker::new("payments", health_check_fn(pass)).group("external"),
+            HealthChecker::new("db", counting(&calls, fail))
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+        ]);
+        let report = actuator.health_group("external").await;
+        assert_eq!(report.status(), HealthStatus::Up);
+        assert_eq!(report.checks().keys().collect::<Vec<_>>(), ["payments"]);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let report = actuator.health_group("startup").await;
+        assert_eq!(report.status(), HealthStatus::Up);
+        assert!(report.checks().is_empty());
+
+        assert_eq!(actuator.health().await.status(), HealthStatus::Down);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(actuator.liveness().await.status(), HealthStatus::Down);
+        assert!(!actuator.liveness().await.checks().contains_key("payments"));
+    }
+
+    #[tokio::test]
+    async fn test_readiness_failure_keeps_liveness_up() {
+        let actuator = actuator(vec![
+            HealthChecker::new("db", health_check_fn(fail)).group(HealthGroup::Readiness),
+            HealthChecker::new("disk", health_check_fn(pass))
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+        ]);
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = actuator.liveness().await;
+        assert_eq!(status, HealthStatus::Up);
+        assert!(!data.contains_key("db"));
+        assert!(data["disk"].success);
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = actuator.readiness().await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(!data["db"].success);
+        assert_eq!(actuator.health().await.status, HealthStatus::Down);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_health_blocking() {
+        let actuator = actuator(vec![
+            HealthChecker::new("db", health_check_fn(fail))
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+        ]);
+        let report = actuator.health_blocking().unwrap();
+        assert_eq!(report.status, HealthStatus::Down);
+        assert_eq!(report.checks["db"].error.as_deref(), Some("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_set_ready() {
+        let actuator = actuator(vec![
+            HealthChecker::new("db", health_check_fn(pass))
+                .group(HealthGroup::Liveness)
+                .group(HealthGroup::Readiness),
+        ]);
+        assert_eq!(actuator.readiness().await.status, HealthStatus::Up);
+        actuator.set_ready(false);
+        assert_eq!(actuator.readiness().await.status, HealthStatus::Down);
+        assert_eq!(actuator.health().await.status, HealthStatus::Down);
+        assert_eq!(actuator.liveness().await.status, HealthStatus::Up);
+        actuator.set_ready(true);
+        assert_eq!(actuator.readiness().await.status, HealthStatus::Up);
+    }
+
+    #[tokio::test]
+    async fn test_liveness_startup_grace() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .health(
+                HealthConfig::default()
+                    .startup_grace(Duration::from_millis(50))
+                    .checker(
+                        HealthChecker::new("db", health_check_fn(fail))
+                            .group(HealthGroup::Liveness),
+                    ),
+            )
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let report = actuator.liveness().await;
+        assert_eq!(report.status, HealthStatus::Up);
+        assert!(report.checks.is_empty());
+        assert_eq!(actuator.health().await.status, HealthStatus::Down);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let report = actuator.liveness().await;
+        assert_eq!(report.status, HealthStatus::Down);
+        assert!(!report.checks["db"].success);
+    }
+
+    #[tokio::test]
+    async fn test_register_health_check() {
+        let actuator = actuator(vec![]);
+        assert!(actuator.health().await.checks.is_empty());
+        actuator.register_health_check(
+            HealthChecker::new("plugin", health_check_fn(fail)).group(HealthGroup::Readiness),
+        );
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = actuator.health().await;
+        assert_eq!(status, HealthStatus::Down);
+        assert!(!data["plugin"].success);
+        assert_eq!(actuator.readiness().await.status, HealthStatus::Down);
+        assert!(actuator.deregister_health_check("plugin"));
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = actuator.health().await;
+        assert_eq!(status, HealthStatus::Up);
+        assert!(data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_health_checker_built_fluently() {
+        let actuator = actuator(vec![]);
+        actuator.register_health_check(
+            HealthChecker::new("cache", health_check_fn(fail))
+                .mandatory(false)
+                .timeout(Duration::from_secs(1))
+                .group(HealthGroup::Readiness),
+        );
+        actuator.register_health_check(
+            HealthChecker::new("memory", MemoryHealthCheck::new(100.0)).group("system"),
+        );
+        let report = actuator.health().await;
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(!report.checks["cache"].is_mandatory);
+        assert!(report.checks["memory"].success);
+        assert_eq!(actuator.readiness().await.checks.len(), 1);
+        assert_eq!(actuator.health_group("system").await.checks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_health_summary() {
+        let actuator = actuator(vec![]);
+        assert!(actuator.health_summary().await.is_empty());
+        for (key, func) in [("db", pass as HealthCheckFn<_>), ("cache", fail)] {
+            actuator.register_health_check(
+                HealthChecker::new(key, health_check_fn(func)).mandatory(false),
+            );
+        }
+        assert_eq!(
+            actuator.health_summary().await,
+            [("cache".to_string(), false), ("db".to_string(), true)]
+        );
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_response_schemas() {
+        let schemas = Actuator::response_schemas();
+        assert_eq!(
+            schemas.keys().collect::<Vec<_>>(),
+            ["health", "info", "metrics"]
+        );
+        let health = &schemas["health"];
+        assert_eq!(
+            health.pointer("/properties/status/$ref").unwrap(),
+            "#/$defs/HealthStatus"
+        );
+        let states: Vec<_> = health
+            .pointer("/$defs/HealthStatus/oneOf")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|state| state["const"].as_str().unwrap())
+            .collect();
+        assert_eq!(states, ["UP", "DEGRADED", "DOWN"]);
+        let required = health.pointer("/$defs/HealthInfo/required").unwrap();
+        assert_eq!(
+            *required,
+            serde_json::json!(["key", "is_mandatory", "success", "duration_ms"])
+        );
+
+        // The hand written schemas list every serialized field.
+        let keys = |value: &serde_json::Value| {
+            let mut keys: Vec<_> = value.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        let actuator = actuator(vec![]);
+        let responses = [
+            (
+                "/properties",
+                serde_json::to_value(actuator.metrics().unwrap()),
+            ),
+            (
+                "/$defs/ApplicationInfo/properties",
+                serde_json::to_value(actuator.info().application()),
+            ),
+        ];
+        let schemas = [&schemas["metrics"], &schemas["info"]];
+        for (schema, (pointer, response)) in schemas.into_iter().zip(responses) {
+            let properties = schema.pointer(pointer).unwrap();
+            assert_eq!(keys(properties), keys(&response.unwrap()), "{pointer}");
+        }
+    }
+
+    #[test]
+    fn test_health_status_code() {
+        let actuator = actuator(vec![]);
+        assert_eq!(actuator.health_status_code(HealthStatus::Up), 200);
+        assert_eq!(actuator.health_status_code(HealthStatus::Degraded), 200);
+        assert_eq!(actuator.health_status_code(HealthStatus::Down), 503);
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .health_status_code(HealthStatus::Degraded, 503)
+            .health_status_code(HealthStatus::Down, 500)
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        assert_eq!(actuator.health_status_code(HealthStatus::Up), 200);
+        assert_eq!(actuator.health_status_code(HealthStatus::Degraded), 503);
+        assert_eq!(actuator.health_status_code(HealthStatus::Down), 500);
+    }
+
+    #[test]
+    fn test_health_check_keys() {
+        let actuator = actuator(vec![
+            HealthChecker::new("db", health_check_fn(fail)),
+            HealthChecker::new("cache", health_check_fn(fail)).mandatory(false),
+        ]);
+        assert_eq!(
+            actuator.health_check_keys(),
+            [("db".to_string(), true), ("cache".to_string(), false)]
+        );
+        actuator.register_health_check(HealthChecker::new("search", health_check_fn(pass)));
+        actuator.register_health_check(
+            HealthChecker::new("db", health_check_fn(pass)).mandatory(false),
+        );
+        assert_eq!(
+            actuator.health_check_keys(),
+            [
+                ("cache".to_string(), false),
+                ("search".to_string(), true),
+                ("db".to_string(), false),
+            ]
+        );
+    }
+
+    #[derive(Debug)]
+    struct DbError;
+
+    impl std::fmt::Display for DbError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("db unreachable")
+        }
+    }
+
+    impl Error for DbError {}
+
+    #[derive(Debug)]
+    enum CacheError {
+        Evicted(u32),
+    }
+
+    impl std::fmt::Display for CacheError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                CacheError::Evicted(count) => write!(f, "{count} keys evicted"),
+            }
+        }
+    }
+
+    impl Error for CacheError {}
+
+    fn db_down() -> BoxFuture<'static, Result<(), DbError>> {
+        async { Err(DbError) }.boxed()
+    }
+
+    fn cache_evicted() -> BoxFuture<'static, Result<(), CacheError>> {
+        async { Err(CacheError::Evicted(3)) }.boxed()
+    }
+
+    #[tokio::test]
+    async fn test_health_custom_errors() {
+        let db = Actuator::new(
+            Config::builder()
+                .name("orders")
+                .port(8080)
+                .health(
+                    HealthConfig::default().checker(
+                        HealthChecker::new("db", health_check_fn(db_down))
+                            .group(HealthGroup::Liveness)
+                            .group(HealthGroup::Readiness),
+                    ),
+                )
+                .build()
+                .unwrap(),
+        );
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = db.health().await;
+        assert_eq!(status, HealthStatus::Down);
+        assert_eq!(data["db"].error.as_deref(), Some("db unreachable"));
+
+        let cache = Actuator::new(
+            Config::builder()
+                .name("orders")
+                .port(8080)
+                .health(
+                    HealthConfig::default().checker(
+                        HealthChecker::new("cache", health_check_fn(cache_evicted))
+                            .mandatory(false)
+                            .group(HealthGroup::Liveness)
+                            .group(HealthGroup::Readiness),
+                    ),
+                )
+                .build()
+                .unwrap(),
+        );
+        let HealthReport {
+            checks: data,
+            status,
+            ..
+        } = cache.health().await;
+        assert_eq!(status, HealthStatus::Degraded);
+        assert_eq!(data["cache"].error.as_deref(), Some("3 keys evicted"));
+    }
+
+    /// Builds an actuator whose exit handler records the exit codes.
+    fn exiting(builder: ConfigBuilder) -> (Actuator, mpsc::Receiver<i32>) {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let cfg = builder
+            .name("orders")
+            .port(8080)
+            .with_exit_handler(move |code| tx.lock().unwrap().send(code).unwrap())
+            .build()
+            .unwrap();
+        (Actuator::new(cfg), rx)
+    }
+
+    #[tokio::test]
+    async fn test_metrics_async() {
+        let actuator = actuator(vec![]);
+        let blocking = actuator.metrics().unwrap();
+        let metrics = actuator.metrics_async().await.unwrap();
+        assert_eq!(metrics.pid, blocking.pid);
+        assert_eq!(metrics.total_memory, blocking.total_memory);
+        assert_eq!(metrics.per_core_usage.len(), blocking.per_core_usage.len());
+        assert_eq!(
+            metrics.load_avg_one.is_some(),
+            blocking.load_avg_one.is_some()
+        );
+        assert_eq!(
+            metrics
+                .disks
+                .iter()
+                .map(|disk| &disk.mount_point)
+                .collect::<Vec<_>>(),
+            blocking
+                .disks
+                .iter()
+                .map(|disk| &disk.mount_point)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// Source reporting fixed numbers.
+    struct FixedSource;
+
+    impl SystemSource for FixedSource {
+        fn total_memory(&self) -> u64 {
+            8_000
+        }
+
+        fn used_memory(&self) -> u64 {
+            2_000
+        }
+
+        fn total_swap(&self) -> u64 {
+            1_000
+        }
+
+        fn used_swap(&self) -> u64 {
+            500
+        }
+
+        fn global_cpu_usage(&self) -> f32 {
+            12.5
+        }
+
+        fn per_core_usage(&self) -> Vec<f32> {
+            vec![10.0, 15.0]
+        }
+    }
+
+    #[test]
+    fn test_metrics_system_source() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .system_source(FixedSource)
+            .build()
+            .unwrap();
+        let metrics = Actuator::new(cfg).metrics().unwrap();
+        assert_eq!(metrics.total_memory(), 8_000);
+        assert_eq!(metrics.used_memory(), 2_000);
+        assert_eq!(metrics.memory_usage_percent(), 25.0);
+        assert_eq!(metrics.total_swap(), 1_000);
+        assert_eq!(metrics.used_swap(), 500);
+        assert_eq!(metrics.swap_usage_percent(), 50.0);
+        assert_eq!(metrics.global_cpu_usage(), 12.5);
+        assert_eq!(metrics.per_core_usage(), [10.0, 15.0]);
+        assert_eq!(metrics.pid(), std::process::id());
+    }
+
+    #[test]
+    fn test_metrics_poisoned() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .system_source(FixedSource)
+            .cpu_usage_window(3)
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        let inner = actuator.inner.clone();
+        let _ = thread::spawn(move || {
+            let _source = inner.metrics_source.lock().unwrap();
+            let _cpu_average = inner.cpu_average.as_ref().unwrap().lock().unwrap();
+            panic!("poisoned");
+        })
+        .join();
+        assert!(actuator.inner.metrics_source.is_poisoned());
+        assert_eq!(actuator.metrics().unwrap().total_memory(), 8_000);
+    }
+
+    #[test]
+    fn test_metrics_cpu_usage_window() {
+        assert!(actuator(vec![]).inner.cpu_average.is_none());
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .cpu_usage_window(3)
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        for _ in 0..4 {
+            thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+            assert!((0.0..=100.0).contains(&actuator.metrics().unwrap().global_cpu_usage));
+        }
+        assert!(actuator.inner.cpu_average.is_some());
+    }
+
+    #[test]
+    fn test_metrics_sampler() {
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .metrics_history_size(2)
+            .build()
+            .unwrap();
+        let actuator = Actuator::new(cfg);
+        assert!(actuator.metrics_history().is_empty());
+
+        actuator
+            .start_metrics_sampler(Duration::from_millis(1))
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while actuator.metrics_history().len() < 2 {
+            assert!(Instant::now() < deadline, "history did not fill up");
+            thread::sleep(Duration::from_millis(5));
+        }
+        let history = actuator.metrics_history();
+        assert_eq!(history.len(), 2);
+        assert!(
+            history
+                .iter()
+                .all(|metrics| metrics.pid == std::process::id())
+        );
+    }
+
+    #[test]
+    fn test_shutdown_invokes_exit_handler_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cfg = Config::builder()
+            .name("orders")
+            .port(8080)
+            .with_exit_handler(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+        Actuator::new(cfg).shutdown();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_shutdown_callback_strategy() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let (actuator, exits) =
+            exiting(Config::builder().shutdown_strategy(ShutdownStrategy::Callback(tx)));
+        let (hooks, ran) = mpsc::channel();
+        actuator.on_shutdown(Box::new(move || hooks.send(()).unwrap()));
+
+        actuator.shutdown();
+        assert_eq!(ran.try_recv(), Ok(()));
+        assert_eq!(rx.try_recv(), Ok(0));
+        actuator.shutdown_with_code(3);
+        assert_eq!(rx.try_recv(), Ok(3));
+        assert!(exits.try_recv().is_err());
+
+        drop(rx);
+        actuator.shutdown();
+        assert!(exits.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_shutdown_exit_strategy_code() {
+        let (actuator, exits) =
+            exiting(Config::builder().shutdown_strategy(ShutdownStrategy::Exit(2)));
+        actuator.shutdown();
+        assert_eq!(exits.try_recv(), Ok(2));
+        actuator.shutdown_with_code(5);
+        assert_eq!(exits.try_recv(), Ok(5));
+    }
+
+    #[test]
+    fn test_shutdown_runs_hooks_in_reverse_order() {
+        let (actuator, exits) = exiting(Config::builder());
+        let (tx, rx) = mpsc::channel();
+        for hook in 1..=3 {
+            let tx = tx.clone();
+            actuator.on_shutdown(Box::new(move || tx.send(hook).unwrap()));
+        }
+        actuator.on_shutdown(Box::new(|| panic!("hook failed")));
+
+        actuator.shutdown_with_code(3);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [3, 2, 1]);
+        assert_eq!(exits.try_iter().collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn test_shutdown_does_not_wait_for_hanging_hook() {
+        let (actuator, exits) =
+            exiting(Config::builder().shutdown_timeout(Duration::from_millis(50)));
+        actuator.on_shutdown(Box::new(|| thread::sleep(Duration::from_secs(60))));
+
+        let start = std::time::Instant::now();
+        actuator.shutdown();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(exits.try_iter().collect::<Vec<_>>(), [0]);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_metrics_prometheus() {
+        let text = actuator(vec![]).metrics_prometheus().unwrap();
+        assert!(text.contains("actuator_memory_total_bytes{name=\"orders\",env=\"test\"} "));
+    }
+
+    #[test]
+    fn test_thread_dump() {
+        let actuator = actuator(vec![]);
+        assert!(!actuator.thread_dump().is_empty());
+        assert!(
+            actuator
+                .thread_dump_string()
+                .contains("rust_actuator::Actuator<E>::thread_dump_string")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_thread_dump_async() {
+        let stacks = actuator(vec![]).thread_dump_async().await;
+        assert!(!stacks[0].frames.is_empty());
+        assert_ne!(stacks[0].name.as_deref(), thread::current().name());
     }
 }
@@ -0,0 +1,1624 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use sysinfo::{Disks, Pid, ProcessesToUpdate, System};
+
+use crate::error::ActuatorError;
+
+/// Source of the memory, swap and CPU numbers of the host the metrics report,
+/// memory values are in bytes and CPU usage in percent.
+///
+/// [`sysinfo::System`] is the default one, another can be set through
+/// [`ConfigBuilder::system_source`](crate::ConfigBuilder::system_source), e.g.
+/// to report controlled values in tests.
+pub trait SystemSource: Send + Sync {
+    /// Reads the numbers anew, called before each collection of the metrics.
+    fn refresh(&mut self) {}
+
+    /// Total memory of the host.
+    fn total_memory(&self) -> u64;
+
+    /// Memory used on the host.
+    fn used_memory(&self) -> u64;
+
+    /// Total swap of the host.
+    fn total_swap(&self) -> u64;
+
+    /// Swap used on the host.
+    fn used_swap(&self) -> u64;
+
+    /// Usage of all the cores.
+    fn global_cpu_usage(&self) -> f32;
+
+    /// Usage of every logical core.
+    fn per_core_usage(&self) -> Vec<f32>;
+}
+
+impl SystemSource for System {
+    fn refresh(&mut self) {
+        self.refresh_memory();
+        self.refresh_cpu_all();
+    }
+
+    fn total_memory(&self) -> u64 {
+        System::total_memory(self)
+    }
+
+    fn used_memory(&self) -> u64 {
+        System::used_memory(self)
+    }
+
+    fn total_swap(&self) -> u64 {
+        System::total_swap(self)
+    }
+
+    fn used_swap(&self) -> u64 {
+        System::used_swap(self)
+    }
+
+    fn global_cpu_usage(&self) -> f32 {
+        System::global_cpu_usage(self)
+    }
+
+    fn per_core_usage(&self) -> Vec<f32> {
+        self.cpus().iter().map(|cpu| cpu.cpu_usage()).collect()
+    }
+}
+
+/// Source of the host numbers along with the system refreshing the current
+/// process. The latter is kept across collections since sysinfo measures the
+/// CPU usage of a process between two refreshes of the same system.
+pub(crate) struct MetricsSource {
+    host: Box<dyn SystemSource>,
+    process: System,
+}
+
+impl MetricsSource {
+    pub(crate) fn new(host: Box<dyn SystemSource>) -> Self {
+        Self {
+            host,
+            process: System::new(),
+        }
+    }
+
+    /// Takes a snapshot of the metrics as [`Metrics::collect`] does.
+    pub(crate) fn collect(
+        &mut self,
+        disk_mount_point: Option<&str>,
+    ) -> Result<Metrics, ActuatorError> {
+        Metrics::collect(&mut *self.host, &mut self.process, disk_mount_point)
+    }
+}
+
+/// Source shared by the actuator and its metrics sampler.
+pub(crate) type SharedMetricsSource = Arc<Mutex<MetricsSource>>;
+
+/// Memory and CPU usage of the host and of the current process, load average
+/// of the host and space of the disks, memory and space values are in bytes.
+///
+/// When serialized, every memory and space value is accompanied by a human
+/// readable `<field>_human` form such as `"16.0 GiB"`. The host uptime is
+/// rendered as whole seconds under `host_uptime_seconds`, the boot time as
+/// RFC 3339.
+///
+/// CPU usage is measured between two refreshes of sysinfo, so the global and
+/// per core values of the first snapshot taken by an actuator are only
+/// accurate once a warm-up refresh happened at least
+/// [`sysinfo::MINIMUM_CPU_UPDATE_INTERVAL`] earlier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    pub(crate) total_memory: u64,
+    pub(crate) used_memory: u64,
+    pub(crate) memory_usage_percent: f32,
+    pub(crate) total_swap: u64,
+    pub(crate) used_swap: u64,
+    pub(crate) swap_usage_percent: f32,
+    pub(crate) global_cpu_usage: f32,
+    /// Usage of every logical core in percent, in the order of sysinfo.
+    pub(crate) per_core_usage: Vec<f32>,
+    pub(crate) process_memory: u64,
+    pub(crate) process_virtual_memory: u64,
+    pub(crate) process_cpu_usage: f32,
+    pub(crate) pid: u32,
+    pub(crate) disks: Vec<DiskMetric>,
+    pub(crate) load_avg_one: Option<f64>,
+    pub(crate) load_avg_five: Option<f64>,
+    pub(crate) load_avg_fifteen: Option<f64>,
+    pub(crate) open_fds: Option<u64>,
+    pub(crate) max_fds: Option<u64>,
+    pub(crate) thread_count: Option<usize>,
+    pub(crate) thread_states: Option<BTreeMap<String, usize>>,
+    pub(crate) allocator_allocated: Option<u64>,
+    pub(crate) allocator_resident: Option<u64>,
+    pub(crate) cgroup_memory_limit: Option<u64>,
+    pub(crate) container_memory_usage_percent: Option<f32>,
+    pub(crate) cgroup_cpu_quota: Option<f64>,
+    pub(crate) host_uptime: Duration,
+    pub(crate) boot_time: SystemTime,
+}
+
+/// Space of a single disk, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskMetric {
+    pub(crate) mount_point: String,
+    pub(crate) total_space: u64,
+    pub(crate) available_space: u64,
+    pub(crate) file_system: String,
+}
+
+impl DiskMetric {
+    /// Path the disk is mounted at.
+    pub fn mount_point(&self) -> &str {
+        &self.mount_point
+    }
+
+    /// Total space of the disk.
+    pub fn total_space(&self) -> u64 {
+        self.total_space
+    }
+
+    /// Space available on the disk.
+    pub fn available_space(&self) -> u64 {
+        self.available_space
+    }
+
+    /// File system of the disk, such as `ext4`.
+    pub fn file_system(&self) -> &str {
+        &self.file_system
+    }
+}
+
+/// Binary unit a byte count can be expressed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteUnit {
+    Kib,
+    Mib,
+    Gib,
+}
+
+impl ByteUnit {
+    fn bytes(self) -> f64 {
+        match self {
+            Self::Kib => 1024.0,
+            Self::Mib => 1024.0 * 1024.0,
+            Self::Gib => 1024.0 * 1024.0 * 1024.0,
+        }
+    }
+
+    /// Expresses the byte count in this unit.
+    pub fn convert(self, bytes: u64) -> f64 {
+        bytes as f64 / self.bytes()
+    }
+}
+
+/// Memory value of [`Metrics`] to express in a [`ByteUnit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryField {
+    TotalMemory,
+    UsedMemory,
+    TotalSwap,
+    UsedSwap,
+    ProcessMemory,
+    ProcessVirtualMemory,
+}
+
+impl Metrics {
+    /// Total memory of the host.
+    pub fn total_memory(&self) -> u64 {
+        self.total_memory
+    }
+
+    /// Memory used on the host.
+    pub fn used_memory(&self) -> u64 {
+        self.used_memory
+    }
+
+    /// Memory used on the host in percent of the total.
+    pub fn memory_usage_percent(&self) -> f32 {
+        self.memory_usage_percent
+    }
+
+    /// Total swap of the host.
+    pub fn total_swap(&self) -> u64 {
+        self.total_swap
+    }
+
+    /// Swap used on the host.
+    pub fn used_swap(&self) -> u64 {
+        self.used_swap
+    }
+
+    /// Swap used on the host in percent of the total.
+    pub fn swap_usage_percent(&self) -> f32 {
+        self.swap_usage_percent
+    }
+
+    /// CPU usage of the host in percent.
+    pub fn global_cpu_usage(&self) -> f32 {
+        self.global_cpu_usage
+    }
+
+    /// Usage of every logical core in percent, in the order of sysinfo.
+    pub fn per_core_usage(&self) -> &[f32] {
+        &self.per_core_usage
+    }
+
+    /// Resident memory of the process.
+    pub fn process_memory(&self) -> u64 {
+        self.process_memory
+    }
+
+    /// Virtual memory of the process.
+    pub fn process_virtual_memory(&self) -> u64 {
+        self.process_virtual_memory
+    }
+
+    /// CPU usage of the process in percent since the previous collection of the
+    /// actuator, zero on the first one.
+    pub fn process_cpu_usage(&self) -> f32 {
+        self.process_cpu_usage
+    }
+
+    /// Identifier of the process.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Space of the reported disks.
+    pub fn disks(&self) -> &[DiskMetric] {
+        &self.disks
+    }
+
+    /// One minute load average of the host, only available on Unix.
+    pub fn load_avg_one(&self) -> Option<f64> {
+        self.load_avg_one
+    }
+
+    /// Five minute load average of the host, only available on Unix.
+    pub fn load_avg_five(&self) -> Option<f64> {
+        self.load_avg_five
+    }
+
+    /// Fifteen minute load average of the host, only available on Unix.
+    pub fn load_avg_fifteen(&self) -> Option<f64> {
+        self.load_avg_fifteen
+    }
+
+    /// Number of file descriptors the process has open, only available on
+    /// Linux.
+    pub fn open_fds(&self) -> Option<u64> {
+        self.open_fds
+    }
+
+    /// Soft limit of the file descriptors the process may open, only available
+    /// on Linux.
+    pub fn max_fds(&self) -> Option<u64> {
+        self.max_fds
+    }
+
+    /// Number of threads of the process, only available on Linux and macOS.
+    pub fn thread_count(&self) -> Option<usize> {
+        self.thread_count
+    }
+
+    /// Number of threads of the process in each scheduling state, such as
+    /// `running`, `sleeping` or `disk_sleep`, only available on Linux.
+    pub fn thread_states(&self) -> Option<&BTreeMap<String, usize>> {
+        self.thread_states.as_ref()
+    }
+
+    /// Bytes allocated by the application according to jemalloc, only available
+    /// with the `jemalloc` feature.
+    pub fn allocator_allocated(&self) -> Option<u64> {
+        self.allocator_allocated
+    }
+
+    /// Bytes of the physical memory mapped by jemalloc, only available with the
+    /// `jemalloc` feature.
+    pub fn allocator_resident(&self) -> Option<u64> {
+        self.allocator_resident
+    }
+
+    /// Memory limit of the cgroup of the process in bytes, only available on
+    /// Linux when the cgroup limits the memory below the total of the host.
+    pub fn cgroup_memory_limit(&self) -> Option<u64> {
+        self.cgroup_memory_limit
+    }
+
+    /// Memory used by the cgroup of the process in percent of its limit, the
+    /// usage that matters inside a container, available with
+    /// [`cgroup_memory_limit`](Self::cgroup_memory_limit).
+    pub fn container_memory_usage_percent(&self) -> Option<f32> {
+        self.container_memory_usage_percent
+    }
+
+    /// CPU quota of the cgroup of the process in cores, only available on Linux
+    /// when the cgroup v2 limits the CPU.
+    pub fn cgroup_cpu_quota(&self) -> Option<f64> {
+        self.cgroup_cpu_quota
+    }
+
+    /// Time elapsed since the host booted.
+    pub fn host_uptime(&self) -> Duration {
+        self.host_uptime
+    }
+
+    /// When the host booted.
+    pub fn boot_time(&self) -> SystemTime {
+        self.boot_time
+    }
+
+    /// Flattens the values into a map keyed by field name, for generic
+    /// iteration. Core usages are keyed `per_core_usage.<index>`, thread states
+    /// `thread_states.<state>` and disk spaces `disks.<mount point>.total_space`
+    /// and `disks.<mount point>.available_space`, unavailable values are left
+    /// out.
+    pub fn to_map(&self) -> BTreeMap<String, f64> {
+        let mut map: BTreeMap<_, _> = [
+            ("total_memory", Some(self.total_memory as f64)),
+            ("used_memory", Some(self.used_memory as f64)),
+            (
+                "memory_usage_percent",
+                Some(f64::from(self.memory_usage_percent)),
+            ),
+            ("total_swap", Some(self.total_swap as f64)),
+            ("used_swap", Some(self.used_swap as f64)),
+            (
+                "swap_usage_percent",
+                Some(f64::from(self.swap_usage_percent)),
+            ),
+            ("global_cpu_usage", Some(f64::from(self.global_cpu_usage))),
+            ("process_memory", Some(self.process_memory as f64)),
+            (
+                "process_virtual_memory",
+                Some(self.process_virtual_memory as f64),
+            ),
+            ("process_cpu_usage", Some(f64::from(self.process_cpu_usage))),
+            ("pid", Some(f64::from(self.pid))),
+            ("load_avg_one", self.load_avg_one),
+            ("load_avg_five", self.load_avg_five),
+            ("load_avg_fifteen", self.load_avg_fifteen),
+            ("open_fds", self.open_fds.map(|fds| fds as f64)),
+            ("max_fds", self.max_fds.map(|fds| fds as f64)),
+            ("thread_count", self.thread_count.map(|count| count as f64)),
+            (
+                "allocator_allocated",
+                self.allocator_allocated.map(|bytes| bytes as f64),
+            ),
+            (
+                "allocator_resident",
+                self.allocator_resident.map(|bytes| bytes as f64),
+            ),
+            (
+                "cgroup_memory_limit",
+                self.cgroup_memory_limit.map(|limit| limit as f64),
+            ),
+            (
+                "container_memory_usage_percent",
+                self.container_memory_usage_percent.map(f64::from),
+            ),
+            ("cgroup_cpu_quota", self.cgroup_cpu_quota),
+            (
+                "host_uptime_seconds",
+                Some(self.host_uptime.as_secs() as f64),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .collect();
+        for (index, usage) in self.per_core_usage.iter().enumerate() {
+            map.insert(format!("per_core_usage.{index}"), f64::from(*usage));
+        }
+        for (state, count) in self.thread_states.iter().flatten() {
+            map.insert(format!("thread_states.{state}"), *count as f64);
+        }
+        for disk in &self.disks {
+            let prefix = format!("disks.{}", disk.mount_point);
+            map.insert(format!("{prefix}.total_space"), disk.total_space as f64);
+            map.insert(
+                format!("{prefix}.available_space"),
+                disk.available_space as f64,
+            );
+        }
+        map
+    }
+
+    /// Returns the memory value in the unit.
+    pub fn as_unit(&self, field: MemoryField, unit: ByteUnit) -> f64 {
+        let bytes = match field {
+            MemoryField::TotalMemory => self.total_memory,
+            MemoryField::UsedMemory => self.used_memory,
+            MemoryField::TotalSwap => self.total_swap,
+            MemoryField::UsedSwap => self.used_swap,
+            MemoryField::ProcessMemory => self.process_memory,
+            MemoryField::ProcessVirtualMemory => self.process_virtual_memory,
+        };
+        unit.convert(bytes)
+    }
+
+    /// Returns the total memory of the host in MiB.
+    pub fn total_memory_mib(&self) -> f64 {
+        self.as_unit(MemoryField::TotalMemory, ByteUnit::Mib)
+    }
+
+    /// Returns the used memory of the host in GiB.
+    pub fn used_memory_gib(&self) -> f64 {
+        self.as_unit(MemoryField::UsedMemory, ByteUnit::Gib)
+    }
+
+    /// Takes a snapshot of the host numbers of the refreshed source and of the
+    /// current process refreshed through `process_system`, whose values are
+    /// zero when it cannot be read. The CPU usage of the process covers the
+    /// time since the previous refresh of `process_system`, zero on the first.
+    ///
+    /// The disks are limited to the one mounted at `disk_mount_point` if set.
+    /// The load averages are only available on Unix, the thread count on Linux
+    /// and macOS, the open file descriptors of the process, their soft limit,
+    /// the states of its threads and the memory of its cgroup only on Linux,
+    /// the statistics of jemalloc only with the `jemalloc` feature.
+    ///
+    /// Fails when the source reports no memory or no CPU, as sandboxes denying
+    /// access to the system information do, rather than reporting misleading
+    /// zeros.
+    pub(crate) fn collect(
+        source: &mut dyn SystemSource,
+        process_system: &mut System,
+        disk_mount_point: Option<&str>,
+    ) -> Result<Self, ActuatorError> {
+        source.refresh();
+        let per_core_usage = source.per_core_usage();
+        if source.total_memory() == 0 || per_core_usage.is_empty() {
+            return Err(ActuatorError::new("system information is unavailable"));
+        }
+        let pid = std::process::id();
+        process_system.refresh_processes(ProcessesToUpdate::Some(&[Pid::from_u32(pid)]), true);
+        let process = process_system.process(Pid::from_u32(pid));
+        #[cfg(unix)]
+        let load_avg = {
+            let load_avg = System::load_average();
+            [
+                Some(load_avg.one),
+                Some(load_avg.five),
+                Some(load_avg.fifteen),
+            ]
+        };
+        #[cfg(not(unix))]
+        let load_avg = [None; 3];
+        #[cfg(target_os = "linux")]
+        let (cgroup_memory_limit, cgroup_memory_usage) = {
+            let (limit, usage) = cgroup_memory(|path| std::fs::read_to_string(path).ok());
+            // cgroup v1 reports no limit as a number beyond any host.
+            let limit = limit.filter(|limit| *limit < source.total_memory());
+            (limit, usage)
+        };
+        #[cfg(not(target_os = "linux"))]
+        let (cgroup_memory_limit, cgroup_memory_usage) = (None, None);
+        #[cfg(feature = "jemalloc")]
+        let (allocator_allocated, allocator_resident) = allocator_stats()
+            .map_or((None, None), |(allocated, resident)| {
+                (Some(allocated), Some(resident))
+            });
+        #[cfg(not(feature = "jemalloc"))]
+        let (allocator_allocated, allocator_resident) = (None, None);
+        Ok(Self {
+            total_memory: source.total_memory(),
+            used_memory: source.used_memory(),
+            memory_usage_percent: usage_percent(source.used_memory(), source.total_memory()),
+            total_swap: source.total_swap(),
+            used_swap: source.used_swap(),
+            swap_usage_percent: usage_percent(source.used_swap(), source.total_swap()),
+            global_cpu_usage: source.global_cpu_usage(),
+            per_core_usage,
+            process_memory: process.map_or(0, |process| process.memory()),
+            process_virtual_memory: process.map_or(0, |process| process.virtual_memory()),
+            process_cpu_usage: process.map_or(0.0, |process| process.cpu_usage()),
+            pid,
+            disks: disks()
+                .into_iter()
+                .filter(|disk| {
+                    disk_mount_point.is_none_or(|mount_point| disk.mount_point == mount_point)
+                })
+                .collect(),
+            load_avg_one: load_avg[0],
+            load_avg_five: load_avg[1],
+            load_avg_fifteen: load_avg[2],
+            #[cfg(target_os = "linux")]
+            open_fds: open_fds(),
+            #[cfg(not(target_os = "linux"))]
+            open_fds: None,
+            #[cfg(target_os = "linux")]
+            max_fds: max_fds(),
+            #[cfg(not(target_os = "linux"))]
+            max_fds: None,
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            thread_count: thread_count(),
+            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+            thread_count: None,
+            #[cfg(target_os = "linux")]
+            thread_states: thread_states(),
+            #[cfg(not(target_os = "linux"))]
+            thread_states: None,
+            allocator_allocated,
+            allocator_resident,
+            cgroup_memory_limit,
+            container_memory_usage_percent: cgroup_memory_limit
+                .zip(cgroup_memory_usage)
+                .map(|(limit, usage)| usage_percent(usage, limit)),
+            #[cfg(target_os = "linux")]
+            cgroup_cpu_quota: cgroup_cpu_quota(|path| std::fs::read_to_string(path).ok()),
+            #[cfg(not(target_os = "linux"))]
+            cgroup_cpu_quota: None,
+            host_uptime: Duration::from_secs(System::uptime()),
+            boot_time: SystemTime::UNIX_EPOCH + Duration::from_secs(System::boot_time()),
+        })
+    }
+}
+
+/// Global CPU usage averaged over the last readings, each taken on a refresh
+/// of the same sysinfo system so that it covers the time since the previous
+/// one.
+pub(crate) struct CpuAverage {
+    system: System,
+    readings: VecDeque<f32>,
+    window: usize,
+}
+
+impl CpuAverage {
+    /// Starts measuring from now, averaging up to `window` readings.
+    pub(crate) fn new(window: usize) -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+        Self {
+            system,
+            readings: VecDeque::with_capacity(window),
+            window,
+        }
+    }
+
+    /// Takes a reading and returns the average of the last ones.
+    pub(crate) fn sample(&mut self) -> f32 {
+        self.system.refresh_cpu_usage();
+        self.push(self.system.global_cpu_usage())
+    }
+
+    fn push(&mut self, reading: f32) -> f32 {
+        if self.readings.len() == self.window {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(reading);
+        self.readings.iter().sum::<f32>() / self.readings.len() as f32
+    }
+}
+
+/// Number of file descriptors the process has open.
+#[cfg(target_os = "linux")]
+fn open_fds() -> Option<u64> {
+    let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+    // The directory being listed holds a descriptor of its own.
+    Some(entries.count().saturating_sub(1) as u64)
+}
+
+/// Soft limit of open file descriptors of the process, `None` when unlimited.
+#[cfg(target_os = "linux")]
+fn max_fds() -> Option<u64> {
+    parse_max_open_files(&std::fs::read_to_string("/proc/self/limits").ok()?)
+}
+
+/// Reads the soft limit from the `Max open files` row of `/proc/<pid>/limits`.
+#[cfg(target_os = "linux")]
+fn parse_max_open_files(limits: &str) -> Option<u64> {
+    limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Number of threads of the process.
+#[cfg(target_os = "linux")]
+fn thread_count() -> Option<usize> {
+    parse_threads(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// Number of threads of the process.
+#[cfg(target_os = "macos")]
+fn thread_count() -> Option<usize> {
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+    // SAFETY: proc_taskinfo is plain data, and the kernel writes at most the
+    // size passed into it.
+    let info = unsafe {
+        let mut info: libc::proc_taskinfo = std::mem::zeroed();
+        let written = libc::proc_pidinfo(
+            std::process::id() as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            (&mut info as *mut libc::proc_taskinfo).cast(),
+            size,
+        );
+        if written != size {
+            return None;
+        }
+        info
+    };
+    usize::try_from(info.pti_threadnum).ok()
+}
+
+/// Reads the `Threads:` row of `/proc/<pid>/status`.
+#[cfg(target_os = "linux")]
+fn parse_threads(status: &str) -> Option<usize> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Number of threads of the process in each state. Threads exiting while they
+/// are listed are left out.
+#[cfg(target_os = "linux")]
+fn thread_states() -> Option<BTreeMap<String, usize>> {
+    let mut states = BTreeMap::new();
+    for task in std::fs::read_dir("/proc/self/task").ok()?.flatten() {
+        let Ok(stat) = std::fs::read_to_string(task.path().join("stat")) else {
+            continue;
+        };
+        if let Some(state) = parse_thread_state(&stat) {
+            *states.entry(state.to_string()).or_insert(0) += 1;
+        }
+    }
+    Some(states)
+}
+
+/// Reads the state of `/proc/<pid>/task/<tid>/stat`, which follows the name of
+/// the thread, itself possibly holding parentheses.
+#[cfg(target_os = "linux")]
+fn parse_thread_state(stat: &str) -> Option<&'static str> {
+    let code = stat[stat.rfind(')')? + 1..].split_whitespace().next()?;
+    Some(match code {
+        "R" => "running",
+        "S" => "sleeping",
+        "D" => "disk_sleep",
+        "T" => "stopped",
+        "t" => "tracing_stop",
+        "Z" => "zombie",
+        "X" => "dead",
+        "I" => "idle",
+        "P" => "parked",
+        _ => "other",
+    })
+}
+
+/// Bytes allocated by the application and resident in physical memory
+/// according to jemalloc, whose statistics are refreshed first. Only meaningful
+/// when jemalloc is the global allocator.
+#[cfg(feature = "jemalloc")]
+fn allocator_stats() -> Option<(u64, u64)> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    epoch::advance().ok()?;
+    let allocated = stats::allocated::read().ok()?;
+    let resident = stats::resident::read().ok()?;
+    Some((allocated as u64, resident as u64))
+}
+
+/// Memory limit and usage of the cgroup in bytes, read with `read` from the
+/// cgroup v2 files, or the v1 ones when those do not exist. The limit is `None`
+/// when v2 reports none.
+#[cfg(target_os = "linux")]
+fn cgroup_memory(read: impl Fn(&str) -> Option<String>) -> (Option<u64>, Option<u64>) {
+    let parse = |path| read(path)?.trim().parse().ok();
+    match read("/sys/fs/cgroup/memory.max") {
+        Some(limit) => (
+            limit.trim().parse().ok(),
+            parse("/sys/fs/cgroup/memory.current"),
+        ),
+        None => (
+            parse("/sys/fs/cgroup/memory/memory.limit_in_bytes"),
+            parse("/sys/fs/cgroup/memory/memory.usage_in_bytes"),
+        ),
+    }
+}
+
+/// CPU quota of the cgroup in cores, the quota over the period of the cgroup v2
+/// `cpu.max` read with `read`, `None` when it sets no quota.
+#[cfg(target_os = "linux")]
+fn cgroup_cpu_quota(read: impl Fn(&str) -> Option<String>) -> Option<f64> {
+    let max = read("/sys/fs/cgroup/cpu.max")?;
+    let mut fields = max.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next()?.parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Space of the disks mounted on the host.
+pub(crate) fn disks() -> Vec<DiskMetric> {
+    Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|disk| DiskMetric {
+            mount_point: disk.mount_point().to_string_lossy().into_owned(),
+            total_space: disk.total_space(),
+            available_space: disk.available_space(),
+            file_system: disk.file_system().to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+/// Disk holding the absolute `path`, the one mounted at the longest prefix of
+/// it.
+pub(crate) fn disk_of<'a>(path: &Path, disks: &'a [DiskMetric]) -> Option<&'a DiskMetric> {
+    disks
+        .iter()
+        .filter(|disk| path.starts_with(&disk.mount_point))
+        .max_by_key(|disk| Path::new(&disk.mount_point).components().count())
+}
+
+/// Memory used on the host in percent of the total, as collected in [`Metrics`],
+/// refreshed on a system kept across the calls. Fails when the host reports no
+/// memory, as [`Metrics::collect`] does.
+pub(crate) fn memory_usage_percent() -> Result<f32, ActuatorError> {
+    static SYSTEM: LazyLock<Mutex<System>> = LazyLock::new(|| Mutex::new(System::new()));
+    let mut system = SYSTEM.lock().unwrap_or_else(PoisonError::into_inner);
+    system.refresh_memory();
+    checked_usage_percent(system.used_memory(), system.total_memory())
+}
+
+/// Share of `total` that is `used` in percent, failing when there is no total.
+fn checked_usage_percent(used: u64, total: u64) -> Result<f32, ActuatorError> {
+    if total == 0 {
+        return Err(ActuatorError::new("system information is unavailable"));
+    }
+    Ok(usage_percent(used, total))
+}
+
+/// Share of `total` that is `used` in percent, zero when there is no total.
+fn usage_percent(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (used as f64 / total as f64 * 100.0) as f32
+}
+
+#[cfg(feature = "prometheus")]
+impl Metrics {
+    /// Renders the metrics as gauges in the Prometheus text exposition format,
+    /// each carrying the given labels. Disk gauges are also labelled with the
+    /// mount point, load averages are left out where unavailable.
+    pub(crate) fn prometheus(&self, labels: &[(&str, &str)]) -> String {
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|(name, value)| format!("{name}=\"{}\"", escape_label_value(value)))
+            .collect();
+        let gauges = [
+            (
+                "actuator_memory_used_bytes",
+                "Memory used on the host in bytes.",
+                self.used_memory as f64,
+            ),
+            (
+                "actuator_memory_total_bytes",
+                "Total memory of the host in bytes.",
+                self.total_memory as f64,
+            ),
+            (
+                "actuator_swap_used_bytes",
+                "Swap used on the host in bytes.",
+                self.used_swap as f64,
+            ),
+            (
+                "actuator_swap_total_bytes",
+                "Total swap of the host in bytes.",
+                self.total_swap as f64,
+            ),
+            (
+                "actuator_cpu_usage_percent",
+                "Global CPU usage of the host in percent.",
+                f64::from(self.global_cpu_usage),
+            ),
+            (
+                "actuator_process_memory_bytes",
+                "Resident memory of the process in bytes.",
+                self.process_memory as f64,
+            ),
+            (
+                "actuator_process_virtual_memory_bytes",
+                "Virtual memory of the process in bytes.",
+                self.process_virtual_memory as f64,
+            ),
+            (
+                "actuator_process_cpu_usage_percent",
+                "CPU usage of the process in percent.",
+                f64::from(self.process_cpu_usage),
+            ),
+        ];
+        let mut out = String::new();
+        for (name, help, value) in gauges {
+            write_gauge(&mut out, name, help, [(labels.join(","), value)]);
+        }
+        let load_avgs = [
+            (
+                "actuator_load_average_1m",
+                "One minute load average of the host.",
+                self.load_avg_one,
+            ),
+            (
+                "actuator_load_average_5m",
+                "Five minute load average of the host.",
+                self.load_avg_five,
+            ),
+            (
+                "actuator_load_average_15m",
+                "Fifteen minute load average of the host.",
+                self.load_avg_fifteen,
+            ),
+        ];
+        for (name, help, value) in load_avgs {
+            if let Some(value) = value {
+                write_gauge(&mut out, name, help, [(labels.join(","), value)]);
+            }
+        }
+        let disk_labels: Vec<_> = self
+            .disks
+            .iter()
+            .map(|disk| {
+                let mut labels = labels.clone();
+                labels.push(format!(
+                    "mount_point=\"{}\"",
+                    escape_label_value(&disk.mount_point)
+                ));
+                labels.join(",")
+            })
+            .collect();
+        write_gauge(
+            &mut out,
+            "actuator_disk_total_bytes",
+            "Total space of the disk in bytes.",
+            disk_labels
+                .iter()
+                .zip(&self.disks)
+                .map(|(labels, disk)| (labels.clone(), disk.total_space as f64)),
+        );
+        write_gauge(
+            &mut out,
+            "actuator_disk_available_bytes",
+            "Available space of the disk in bytes.",
+            disk_labels
+                .iter()
+                .zip(&self.disks)
+                .map(|(labels, disk)| (labels.clone(), disk.available_space as f64)),
+        );
+        out
+    }
+}
+
+/// Appends a gauge with a sample per rendered label set.
+#[cfg(feature = "prometheus")]
+fn write_gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, f64)>,
+) {
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+    for (labels, value) in samples {
+        out.push_str(&format!("{name}{{{labels}}} {value}\n"));
+    }
+}
+
+/// Escapes a label value as required by the exposition format.
+#[cfg(feature = "prometheus")]
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Metrics {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Metrics", 33)?;
+        state.serialize_field("total_memory", &self.total_memory)?;
+        state.serialize_field("total_memory_human", &human_bytes(self.total_memory))?;
+        state.serialize_field("used_memory", &self.used_memory)?;
+        state.serialize_field("used_memory_human", &human_bytes(self.used_memory))?;
+        state.serialize_field("memory_usage_percent", &self.memory_usage_percent)?;
+        state.serialize_field("total_swap", &self.total_swap)?;
+        state.serialize_field("total_swap_human", &human_bytes(self.total_swap))?;
+        state.serialize_field("used_swap", &self.used_swap)?;
+        state.serialize_field("used_swap_human", &human_bytes(self.used_swap))?;
+        state.serialize_field("swap_usage_percent", &self.swap_usage_percent)?;
+        state.serialize_field("global_cpu_usage", &self.global_cpu_usage)?;
+        state.serialize_field("per_core_usage", &self.per_core_usage)?;
+        state.serialize_field("process_memory", &self.process_memory)?;
+        state.serialize_field("process_memory_human", &human_bytes(self.process_memory))?;
+        state.serialize_field("process_virtual_memory", &self.process_virtual_memory)?;
+        state.serialize_field(
+            "process_virtual_memory_human",
+            &human_bytes(self.process_virtual_memory),
+        )?;
+        state.serialize_field("process_cpu_usage", &self.process_cpu_usage)?;
+        state.serialize_field("pid", &self.pid)?;
+        state.serialize_field("disks", &self.disks)?;
+        state.serialize_field("load_avg_one", &self.load_avg_one)?;
+        state.serialize_field("load_avg_five", &self.load_avg_five)?;
+        state.serialize_field("load_avg_fifteen", &self.load_avg_fifteen)?;
+        state.serialize_field("open_fds", &self.open_fds)?;
+        state.serialize_field("max_fds", &self.max_fds)?;
+        state.serialize_field("thread_count", &self.thread_count)?;
+        state.serialize_field("thread_states", &self.thread_states)?;
+        state.serialize_field("allocator_allocated", &self.allocator_allocated)?;
+        state.serialize_field("allocator_resident", &self.allocator_resident)?;
+        state.serialize_field("cgroup_memory_limit", &self.cgroup_memory_limit)?;
+        state.serialize_field(
+            "container_memory_usage_percent",
+            &self.container_memory_usage_percent,
+        )?;
+        state.serialize_field("cgroup_cpu_quota", &self.cgroup_cpu_quota)?;
+        state.serialize_field("host_uptime_seconds", &self.host_uptime.as_secs())?;
+        state.serialize_field(
+            "boot_time",
+            &humantime::format_rfc3339(self.boot_time).to_string(),
+        )?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DiskMetric {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("DiskMetric", 6)?;
+        state.serialize_field("mount_point", &self.mount_point)?;
+        state.serialize_field("total_space", &self.total_space)?;
+        state.serialize_field("total_space_human", &human_bytes(self.total_space))?;
+        state.serialize_field("available_space", &self.available_space)?;
+        state.serialize_field("available_space_human", &human_bytes(self.available_space))?;
+        state.serialize_field("file_system", &self.file_system)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Metrics {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Metrics".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let bytes = generator.subschema_for::<u64>();
+        let human = generator.subschema_for::<String>();
+        let percent = generator.subschema_for::<f32>();
+        let load_avg = generator.subschema_for::<Option<f64>>();
+        let fds = generator.subschema_for::<Option<u64>>();
+        crate::schema::object([
+            ("total_memory", bytes.clone()),
+            ("total_memory_human", human.clone()),
+            ("used_memory", bytes.clone()),
+            ("used_memory_human", human.clone()),
+            ("memory_usage_percent", percent.clone()),
+            ("total_swap", bytes.clone()),
+            ("total_swap_human", human.clone()),
+            ("used_swap", bytes.clone()),
+            ("used_swap_human", human.clone()),
+            ("swap_usage_percent", percent.clone()),
+            ("global_cpu_usage", percent.clone()),
+            ("per_core_usage", generator.subschema_for::<Vec<f32>>()),
+            ("process_memory", bytes.clone()),
+            ("process_memory_human", human.clone()),
+            ("process_virtual_memory", bytes),
+            ("process_virtual_memory_human", human),
+            ("process_cpu_usage", percent),
+            ("pid", generator.subschema_for::<u32>()),
+            ("disks", generator.subschema_for::<Vec<DiskMetric>>()),
+            ("load_avg_one", load_avg.clone()),
+            ("load_avg_five", load_avg.clone()),
+            ("load_avg_fifteen", load_avg.clone()),
+            ("open_fds", fds.clone()),
+            ("max_fds", fds.clone()),
+            ("thread_count", generator.subschema_for::<Option<usize>>()),
+            (
+                "thread_states",
+                generator.subschema_for::<Option<BTreeMap<String, usize>>>(),
+            ),
+            ("allocator_allocated", fds.clone()),
+            ("allocator_resident", fds.clone()),
+            ("cgroup_memory_limit", fds),
+            (
+                "container_memory_usage_percent",
+                generator.subschema_for::<Option<f32>>(),
+            ),
+            ("cgroup_cpu_quota", load_avg),
+            ("host_uptime_seconds", crate::schema::whole_units(generator)),
+            ("boot_time", crate::schema::date_time(generator)),
+        ])
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for DiskMetric {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "DiskMetric".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let text = generator.subschema_for::<String>();
+        let bytes = generator.subschema_for::<u64>();
+        let human = generator.subschema_for::<String>();
+        crate::schema::object([
+            ("mount_point", text.clone()),
+            ("total_space", bytes.clone()),
+            ("total_space_human", human.clone()),
+            ("available_space", bytes),
+            ("available_space_human", human),
+            ("file_system", text),
+        ])
+    }
+}
+
+/// Formats a byte count with binary units, e.g. `"16.0 GiB"`.
+#[cfg(feature = "serde")]
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(disk_mount_point: Option<&str>) -> Result<Metrics, ActuatorError> {
+        MetricsSource::new(Box::new(System::new())).collect(disk_mount_point)
+    }
+
+    #[test]
+    fn test_collect_current_process() {
+        let metrics = collect(None).unwrap();
+        assert_eq!(metrics.pid, std::process::id());
+        assert!(metrics.process_memory > 0);
+    }
+
+    /// Source of a sandbox denying access to the system information.
+    struct Unavailable;
+
+    impl SystemSource for Unavailable {
+        fn total_memory(&self) -> u64 {
+            0
+        }
+
+        fn used_memory(&self) -> u64 {
+            0
+        }
+
+        fn total_swap(&self) -> u64 {
+            0
+        }
+
+        fn used_swap(&self) -> u64 {
+            0
+        }
+
+        fn global_cpu_usage(&self) -> f32 {
+            0.0
+        }
+
+        fn per_core_usage(&self) -> Vec<f32> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_collect_unavailable_system() {
+        let err = Metrics::collect(&mut Unavailable, &mut System::new(), None)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "system information is unavailable");
+    }
+
+    #[test]
+    fn test_getters() {
+        let metrics = collect(None).unwrap();
+        assert_eq!(metrics.pid(), std::process::id());
+        assert!(metrics.used_memory() <= metrics.total_memory());
+        assert!((0.0..=100.0).contains(&metrics.memory_usage_percent()));
+        assert_eq!(metrics.per_core_usage().len(), num_cpus::get());
+        for disk in metrics.disks() {
+            assert!(disk.available_space() <= disk.total_space());
+        }
+    }
+
+    #[test]
+    fn test_to_map() {
+        let metrics = collect(None).unwrap();
+        let map = metrics.to_map();
+        assert_eq!(map["used_memory"], metrics.used_memory() as f64);
+        assert_eq!(map["pid"], f64::from(metrics.pid()));
+        assert_eq!(
+            map["global_cpu_usage"],
+            f64::from(metrics.global_cpu_usage())
+        );
+        assert_eq!(
+            map["per_core_usage.0"],
+            f64::from(metrics.per_core_usage()[0])
+        );
+        assert_eq!(
+            map.contains_key("load_avg_one"),
+            metrics.load_avg_one().is_some()
+        );
+        assert_eq!(map.contains_key("open_fds"), metrics.open_fds().is_some());
+        for (state, count) in metrics.thread_states().into_iter().flatten() {
+            assert_eq!(map[&format!("thread_states.{state}")], *count as f64);
+        }
+        for disk in metrics.disks() {
+            let key = format!("disks.{}.total_space", disk.mount_point());
+            assert_eq!(map[&key], disk.total_space() as f64);
+        }
+    }
+
+    #[test]
+    fn test_cpu_average_stabilizes() {
+        let mut average = CpuAverage::new(4);
+        let averages: Vec<_> = [0.0, 100.0, 0.0, 100.0, 0.0, 100.0, 0.0]
+            .into_iter()
+            .map(|reading| average.push(reading))
+            .collect();
+        assert_eq!(averages[..3], [0.0, 50.0, 100.0 / 3.0]);
+        // Once the window is full the alternating readings average out.
+        assert_eq!(averages[3..], [50.0; 4]);
+    }
+
+    #[test]
+    fn test_cpu_average_sample() {
+        let mut average = CpuAverage::new(3);
+        for _ in 0..3 {
+            std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+            assert!((0.0..=100.0).contains(&average.sample()));
+        }
+        assert_eq!(average.readings.len(), 3);
+    }
+
+    #[test]
+    fn test_collect_host_uptime() {
+        let started = SystemTime::now();
+        let metrics = collect(None).unwrap();
+        assert!(metrics.host_uptime > Duration::ZERO);
+        assert!(metrics.boot_time < started);
+        // Both are whole seconds read at slightly different times.
+        let booted = started.duration_since(metrics.boot_time).unwrap();
+        assert!(booted.abs_diff(metrics.host_uptime) <= Duration::from_secs(2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_load_average() {
+        let metrics = collect(None).unwrap();
+        assert!(metrics.load_avg_one.unwrap() >= 0.0);
+        assert!(metrics.load_avg_five.unwrap() >= 0.0);
+        assert!(metrics.load_avg_fifteen.unwrap() >= 0.0);
+    }
+
+    #[test]
+    fn test_collect_per_core_usage() {
+        let metrics = collect(None).unwrap();
+        assert_eq!(metrics.per_core_usage.len(), num_cpus::get());
+        assert!(
+            metrics
+                .per_core_usage
+                .iter()
+                .all(|usage| (0.0..=100.0).contains(usage))
+        );
+    }
+
+    fn burn_cpu(duration: Duration) {
+        let start = std::time::Instant::now();
+        let mut spins = 0u64;
+        while start.elapsed() < duration {
+            spins = std::hint::black_box(spins.wrapping_add(1));
+        }
+    }
+
+    #[test]
+    fn test_collect_process_cpu_usage() {
+        // sysinfo leaves the usage at zero until the process has spent a clock
+        // tick on the CPU.
+        burn_cpu(Duration::from_millis(50));
+        let mut source = MetricsSource::new(Box::new(System::new()));
+        assert_eq!(source.collect(None).unwrap().process_cpu_usage, 0.0);
+        burn_cpu(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL * 2);
+        let usage = source.collect(None).unwrap().process_cpu_usage;
+        assert!(usage > 0.0, "{usage}");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_collect_fds() {
+        let metrics = collect(None).unwrap();
+        assert!(metrics.open_fds.unwrap() >= 3);
+        if let Some(max_fds) = metrics.max_fds {
+            assert!(max_fds >= metrics.open_fds.unwrap());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_max_open_files() {
+        let limits = "\
+Limit                     Soft Limit           Hard Limit           Units
+Max processes             63445                63445                processes
+Max open files            1024                 524288               files
+Max locked memory         8388608              8388608              bytes
+";
+        assert_eq!(parse_max_open_files(limits), Some(1024));
+        assert_eq!(
+            parse_max_open_files(
+                "Max open files            unlimited            unlimited            files"
+            ),
+            None
+        );
+        assert_eq!(parse_max_open_files(""), None);
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[global_allocator]
+    static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+    #[cfg(feature = "jemalloc")]
+    #[test]
+    fn test_collect_allocator_stats() {
+        let buffer = vec![1u8; 1024 * 1024];
+        let metrics = collect(None).unwrap();
+        drop(buffer);
+        assert!(metrics.allocator_allocated.unwrap() >= 1024 * 1024);
+        assert!(metrics.allocator_resident.unwrap() > 0);
+    }
+
+    #[cfg(not(feature = "jemalloc"))]
+    #[test]
+    fn test_collect_no_allocator_stats() {
+        let metrics = collect(None).unwrap();
+        assert_eq!(metrics.allocator_allocated, None);
+        assert_eq!(metrics.allocator_resident, None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_threads() {
+        let status = "Name:\tactuator\nState:\tS (sleeping)\nThreads:\t12\nVmRSS:\t  2048 kB\n";
+        assert_eq!(parse_threads(status), Some(12));
+        assert_eq!(parse_threads("Name:\tactuator\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_thread_state() {
+        assert_eq!(
+            parse_thread_state("42 (tokio-worker) S 1 42"),
+            Some("sleeping")
+        );
+        assert_eq!(parse_thread_state("42 (a) b) R 1 42"), Some("running"));
+        assert_eq!(parse_thread_state("42 (io) D 1"), Some("disk_sleep"));
+        assert_eq!(parse_thread_state("42 (io) W 1"), Some("other"));
+        assert_eq!(parse_thread_state("42 (io)"), None);
+        assert_eq!(parse_thread_state("42 io S"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_collect_thread_count() {
+        let before = collect(None).unwrap().thread_count.unwrap();
+        assert!(before >= 1);
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let stopped = Arc::new(Mutex::new(stopped));
+        let threads: Vec<_> = (0..16)
+            .map(|_| {
+                let stopped = stopped.clone();
+                std::thread::spawn(move || {
+                    let _ = stopped.lock().unwrap().recv();
+                })
+            })
+            .collect();
+        let metrics = collect(None).unwrap();
+        let after = metrics.thread_count.unwrap();
+        let states = metrics.thread_states.unwrap();
+        drop(stop);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert!(after > before, "{after} threads after {before}");
+        // The collecting thread runs, the blocked ones sleep.
+        assert!(states["running"] >= 1, "{states:?}");
+        assert!(states["sleeping"] >= 16, "{states:?}");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cgroup_memory() {
+        let files = |files: &'static [(&'static str, &'static str)]| {
+            move |path: &str| {
+                files
+                    .iter()
+                    .find(|(name, _)| *name == path)
+                    .map(|(_, content)| content.to_string())
+            }
+        };
+        assert_eq!(
+            cgroup_memory(files(&[
+                ("/sys/fs/cgroup/memory.max", "536870912\n"),
+                ("/sys/fs/cgroup/memory.current", "134217728\n"),
+            ])),
+            (Some(536870912), Some(134217728))
+        );
+        assert_eq!(
+            cgroup_memory(files(&[
+                ("/sys/fs/cgroup/memory.max", "max\n"),
+                ("/sys/fs/cgroup/memory.current", "134217728\n"),
+            ])),
+            (None, Some(134217728))
+        );
+        assert_eq!(
+            cgroup_memory(files(&[
+                ("/sys/fs/cgroup/memory/memory.limit_in_bytes", "268435456\n"),
+                ("/sys/fs/cgroup/memory/memory.usage_in_bytes", "67108864\n"),
+            ])),
+            (Some(268435456), Some(67108864))
+        );
+        assert_eq!(cgroup_memory(files(&[])), (None, None));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cgroup_cpu_quota() {
+        let cpu_max = |content: &'static str| {
+            move |path: &str| (path == "/sys/fs/cgroup/cpu.max").then(|| content.to_string())
+        };
+        assert_eq!(cgroup_cpu_quota(cpu_max("150000 100000\n")), Some(1.5));
+        assert_eq!(cgroup_cpu_quota(cpu_max("50000 100000\n")), Some(0.5));
+        assert_eq!(cgroup_cpu_quota(cpu_max("max 100000\n")), None);
+        assert_eq!(cgroup_cpu_quota(cpu_max("")), None);
+        assert_eq!(cgroup_cpu_quota(|_: &str| None), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_collect_cgroup_memory() {
+        let metrics = collect(None).unwrap();
+        match metrics.cgroup_memory_limit {
+            Some(limit) => assert!(limit < metrics.total_memory),
+            None => assert_eq!(metrics.container_memory_usage_percent, None),
+        }
+    }
+
+    #[test]
+    fn test_collect_disks() {
+        let metrics = collect(None).unwrap();
+        assert!(metrics.disks.iter().any(|disk| disk.mount_point == "/"));
+
+        let metrics = collect(Some("/")).unwrap();
+        assert_eq!(metrics.disks.len(), 1);
+        assert!(metrics.disks[0].total_space > 0);
+
+        assert!(collect(Some("/nonexistent")).unwrap().disks.is_empty());
+    }
+
+    #[test]
+    fn test_disk_of() {
+        let disk = |mount_point: &str| DiskMetric {
+            mount_point: mount_point.to_string(),
+            total_space: 0,
+            available_space: 0,
+            file_system: "ext4".to_string(),
+        };
+        let disks = [disk("/"), disk("/var/lib"), disk("/var"), disk("/home")];
+        let mount_point =
+            |path: &str| disk_of(Path::new(path), &disks).map(|disk| disk.mount_point.as_str());
+        assert_eq!(mount_point("/var/lib/postgres"), Some("/var/lib"));
+        assert_eq!(mount_point("/var/lib"), Some("/var/lib"));
+        assert_eq!(mount_point("/var/library"), Some("/var"));
+        assert_eq!(mount_point("/tmp"), Some("/"));
+        assert_eq!(disk_of(Path::new("/tmp"), &disks[1..]), None);
+    }
+
+    fn metrics() -> Metrics {
+        Metrics {
+            total_memory: 16 * 1024 * 1024 * 1024,
+            used_memory: 512 * 1024 * 1024,
+            memory_usage_percent: 3.125,
+            total_swap: 0,
+            used_swap: 0,
+            swap_usage_percent: 0.0,
+            global_cpu_usage: 12.5,
+            per_core_usage: vec![25.0, 0.0],
+            process_memory: 64 * 1024 * 1024,
+            process_virtual_memory: 2 * 1024 * 1024 * 1024,
+            process_cpu_usage: 1.5,
+            pid: 42,
+            disks: vec![DiskMetric {
+                mount_point: "/".to_string(),
+                total_space: 100 * 1024 * 1024 * 1024,
+                available_space: 25 * 1024 * 1024 * 1024,
+                file_system: "ext4".to_string(),
+            }],
+            load_avg_one: Some(0.5),
+            load_avg_five: Some(0.25),
+            load_avg_fifteen: None,
+            open_fds: Some(12),
+            max_fds: Some(1024),
+            thread_count: Some(8),
+            thread_states: Some([("running".to_string(), 1), ("sleeping".to_string(), 7)].into()),
+            allocator_allocated: Some(32 * 1024 * 1024),
+            allocator_resident: None,
+            cgroup_memory_limit: Some(1024 * 1024 * 1024),
+            container_memory_usage_percent: Some(50.0),
+            cgroup_cpu_quota: Some(1.5),
+            host_uptime: Duration::from_millis(86_400_500),
+            boot_time: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn test_usage_percent() {
+        assert_eq!(usage_percent(512, 2048), 25.0);
+        assert_eq!(usage_percent(2048, 2048), 100.0);
+        assert_eq!(usage_percent(0, 2048), 0.0);
+        // Hosts without swap report zero of zero.
+        assert_eq!(usage_percent(0, 0), 0.0);
+        assert_eq!(checked_usage_percent(512, 2048).unwrap(), 25.0);
+        assert_eq!(
+            checked_usage_percent(0, 0).unwrap_err().to_string(),
+            "system information is unavailable"
+        );
+        assert_eq!(usage_percent(512, 0), 0.0);
+    }
+
+    #[test]
+    fn test_collect_usage_percent() {
+        let metrics = collect(None).unwrap();
+        assert!((0.0..=100.0).contains(&metrics.memory_usage_percent));
+        assert!((0.0..=100.0).contains(&metrics.swap_usage_percent));
+        if metrics.total_swap == 0 {
+            assert_eq!(metrics.swap_usage_percent, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_byte_unit_convert() {
+        assert_eq!(ByteUnit::Kib.convert(1536), 1.5);
+        assert_eq!(ByteUnit::Mib.convert(3 * 1024 * 1024), 3.0);
+        assert_eq!(ByteUnit::Gib.convert(512 * 1024 * 1024), 0.5);
+        assert_eq!(ByteUnit::Gib.convert(0), 0.0);
+    }
+
+    #[test]
+    fn test_as_unit() {
+        let metrics = metrics();
+        assert_eq!(metrics.total_memory_mib(), 16384.0);
+        assert_eq!(metrics.used_memory_gib(), 0.5);
+        assert_eq!(
+            metrics.as_unit(MemoryField::ProcessMemory, ByteUnit::Kib),
+            65536.0
+        );
+        assert_eq!(
+            metrics.as_unit(MemoryField::ProcessVirtualMemory, ByteUnit::Gib),
+            2.0
+        );
+        assert_eq!(metrics.as_unit(MemoryField::UsedSwap, ByteUnit::Mib), 0.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(0), "0 B");
+        assert_eq!(human_bytes(512), "512 B");
+        assert_eq!(human_bytes(1536), "1.5 KiB");
+        assert_eq!(human_bytes(16 * 1024 * 1024 * 1024), "16.0 GiB");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let json = serde_json::to_value(metrics()).unwrap();
+        assert_eq!(json["total_memory"], 16u64 * 1024 * 1024 * 1024);
+        assert_eq!(json["total_memory_human"], "16.0 GiB");
+        assert_eq!(json["used_memory_human"], "512.0 MiB");
+        assert_eq!(json["total_swap_human"], "0 B");
+        assert_eq!(json["used_swap"], 0);
+        assert_eq!(json["memory_usage_percent"], 3.125);
+        assert_eq!(json["swap_usage_percent"], 0.0);
+        assert_eq!(json["global_cpu_usage"], 12.5);
+        assert_eq!(json["per_core_usage"], serde_json::json!([25.0, 0.0]));
+        assert_eq!(json["process_memory_human"], "64.0 MiB");
+        assert_eq!(json["process_virtual_memory_human"], "2.0 GiB");
+        assert_eq!(json["process_cpu_usage"], 1.5);
+        assert_eq!(json["pid"], 42);
+        assert_eq!(json["disks"][0]["mount_point"], "/");
+        assert_eq!(json["disks"][0]["total_space_human"], "100.0 GiB");
+        assert_eq!(json["disks"][0]["available_space_human"], "25.0 GiB");
+        assert_eq!(json["disks"][0]["file_system"], "ext4");
+        assert_eq!(json["load_avg_one"], 0.5);
+        assert_eq!(json["load_avg_fifteen"], serde_json::Value::Null);
+        assert_eq!(json["open_fds"], 12);
+        assert_eq!(json["max_fds"], 1024);
+        assert_eq!(json["thread_count"], 8);
+        assert_eq!(json["thread_states"]["sleeping"], 7);
+        assert_eq!(json["allocator_allocated"], 32 * 1024 * 1024);
+        assert_eq!(json["allocator_resident"], serde_json::Value::Null);
+        assert_eq!(json["cgroup_memory_limit"], 1024 * 1024 * 1024);
+        assert_eq!(json["container_memory_usage_percent"], 50.0);
+        assert_eq!(json["cgroup_cpu_quota"], 1.5);
+        assert_eq!(json["host_uptime_seconds"], 86_400);
+        assert_eq!(json["boot_time"], "1970-01-01T00:00:00Z");
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_prometheus() {
+        let text = metrics().prometheus(&[("name", "orders"), ("env", "prod")]);
+        let mut declared = Vec::new();
+        let mut samples = Vec::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# HELP ") {
+                assert!(rest.split_once(' ').is_some(), "{line}");
+            } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = rest.split_once(' ').unwrap();
+                assert_eq!(kind, "gauge");
+                declared.push(name.to_string());
+            } else {
+                let (series, value) = line.rsplit_once(' ').unwrap();
+                let (name, labels) = series.split_once('{').unwrap();
+                assert!(labels.starts_with("name=\"orders\",env=\"prod\""), "{line}");
+                assert!(labels.ends_with('}'), "{line}");
+                assert!(value.parse::<f64>().is_ok(), "{line}");
+                assert_eq!(declared.last().map(String::as_str), Some(name));
+                samples.push(name.to_string());
+            }
+        }
+        assert_eq!(
+            samples,
+            [
+                "actuator_memory_used_bytes",
+                "actuator_memory_total_bytes",
+                "actuator_swap_used_bytes",
+                "actuator_swap_total_bytes",
+                "actuator_cpu_usage_percent",
+                "actuator_process_memory_bytes",
+                "actuator_process_virtual_memory_bytes",
+                "actuator_process_cpu_usage_percent",
+                "actuator_load_average_1m",
+                "actuator_load_average_5m",
+                "actuator_disk_total_bytes",
+                "actuator_disk_available_bytes",
+            ]
+        );
+        assert!(
+            text.contains("actuator_memory_used_bytes{name=\"orders\",env=\"prod\"} 536870912\n")
+        );
+        assert!(text.contains("actuator_cpu_usage_percent{name=\"orders\",env=\"prod\"} 12.5\n"));
+        assert!(text.contains(
+            "actuator_disk_available_bytes{name=\"orders\",env=\"prod\",mount_point=\"/\"} 26843545600\n"
+        ));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("plain"), "plain");
+        assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::error::ActuatorError;
+use crate::metrics::{Metrics, SharedMetricsSource};
+
+/// Takes a snapshot of the metrics every interval on a thread of its own,
+/// keeping the most recent ones. Stops when dropped.
+pub(crate) struct MetricsSampler {
+    history: Arc<Mutex<VecDeque<Metrics>>>,
+    /// Dropped to wake the thread up and let it exit.
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsSampler {
+    /// Starts sampling the source right away, keeping at most `capacity`
+    /// samples. Fails when the thread cannot be spawned.
+    pub(crate) fn start(
+        interval: Duration,
+        capacity: usize,
+        source: SharedMetricsSource,
+        disk_mount_point: Option<String>,
+    ) -> Result<Self, ActuatorError> {
+        let history = Arc::new(Mutex::new(VecDeque::with_capacity(capacity)));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let samples = history.clone();
+        let thread = thread::Builder::new()
+            .name("actuator-metrics".to_string())
+            .spawn(move || {
+                loop {
+                    let metrics = {
+                        let mut source = source.lock().unwrap_or_else(PoisonError::into_inner);
+                        source.collect(disk_mount_point.as_deref())
+                    };
+                    // A sample is skipped when the system information is unavailable.
+                    if let Ok(metrics) = metrics {
+                        let mut samples = samples.lock().unwrap_or_else(PoisonError::into_inner);
+                        if samples.len() == capacity {
+                            samples.pop_front();
+                        }
+                        samples.push_back(metrics);
+                    }
+                    match stopped.recv_timeout(interval) {
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            })?;
+        Ok(Self {
+            history,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the samples taken so far, the oldest first.
+    pub(crate) fn history(&self) -> Vec<Metrics> {
+        let history = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        history.iter().cloned().collect()
+    }
+}
+
+impl Drop for MetricsSampler {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::metrics::MetricsSource;
+
+    fn source() -> SharedMetricsSource {
+        Arc::new(Mutex::new(MetricsSource::new(Box::new(
+            sysinfo::System::new(),
+        ))))
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let sampler = MetricsSampler::start(Duration::from_millis(1), 3, source(), None).unwrap();
+        wait_until(|| sampler.history().len() == 3);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(sampler.history().len(), 3);
+    }
+
+    #[test]
+    fn test_drop_stops_the_thread() {
+        let sampler = MetricsSampler::start(Duration::from_secs(60), 3, source(), None).unwrap();
+        let history = sampler.history.clone();
+        drop(sampler);
+        // The thread released its handle on the samples when it exited.
+        assert_eq!(Arc::strong_count(&history), 1);
+        assert_eq!(history.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,29 @@
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+
+/// Schema of an object made of the properties, all of them required, for the
+/// types serialized by hand.
+pub(crate) fn object<const N: usize>(properties: [(&str, Schema); N]) -> Schema {
+    let required: Vec<_> = properties.iter().map(|(name, _)| *name).collect();
+    let properties: serde_json::Map<_, _> = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema.to_value()))
+        .collect();
+    json_schema!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+/// Schema of a point in time rendered as RFC 3339.
+pub(crate) fn date_time(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "string",
+        "format": "date-time",
+    })
+}
+
+/// Schema of a duration rendered as a whole number of units.
+pub(crate) fn whole_units(generator: &mut SchemaGenerator) -> Schema {
+    u64::json_schema(generator)
+}
@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::health::HealthReport;
+use crate::info::Info;
+use crate::metrics::Metrics;
+
+/// Data of the info, health, env and metrics endpoints gathered at once,
+/// created through [`Actuator::snapshot`](crate::Actuator::snapshot).
+///
+/// The sections of the disabled endpoints are `None`, and left out when
+/// serialized.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Snapshot {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) info: Option<Arc<Info>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) health: Option<HealthReport>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) env: Option<Arc<BTreeMap<String, String>>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) metrics: Option<Metrics>,
+}
+
+impl Snapshot {
+    /// Returns the information, if the info endpoint is enabled.
+    pub fn info(&self) -> Option<&Info> {
+        self.info.as_deref()
+    }
+
+    /// Returns the health report, if the health endpoint is enabled.
+    pub fn health(&self) -> Option<&HealthReport> {
+        self.health.as_ref()
+    }
+
+    /// Returns the masked environment variables, if the env endpoint is enabled.
+    pub fn env(&self) -> Option<&BTreeMap<String, String>> {
+        self.env.as_deref()
+    }
+
+    /// Returns the metrics, if the metrics endpoint is enabled.
+    pub fn metrics(&self) -> Option<&Metrics> {
+        self.metrics.as_ref()
+    }
+}
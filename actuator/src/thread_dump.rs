@@ -0,0 +1,446 @@
+use std::fmt::Write;
+
+/// Stack of a single thread, innermost frame first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ThreadStack {
+    /// Kernel thread id, only known on Linux.
+    pub(crate) id: Option<u64>,
+    pub(crate) name: Option<String>,
+    pub(crate) frames: Vec<ThreadFrame>,
+}
+
+/// A single frame of a stack trace, resolved to its symbol when the debug
+/// information allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ThreadFrame {
+    pub(crate) function: Option<String>,
+    pub(crate) file: Option<String>,
+    pub(crate) line: Option<u32>,
+    pub(crate) address: usize,
+}
+
+/// Captures the stacks of all the threads of the process, the calling one first.
+///
+/// On Linux the other threads are enumerated through `/proc/self/task` and
+/// interrupted with a signal to unwind their own stack. This is best effort, a
+/// thread blocking the signal or not answering in time is reported without
+/// frames. Elsewhere only the calling thread is captured.
+///
+/// The signal handler unwinds through `backtrace::trace_unsynchronized`, which
+/// is not async-signal-safe: a thread interrupted while holding the lock of the
+/// dynamic loader, loading a library or unwinding itself, can deadlock. The
+/// capture also waits up to 100ms on each thread not answering, so it is better
+/// kept off asynchronous executors.
+pub(crate) fn capture_all() -> Vec<ThreadStack> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::capture_all()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        vec![ThreadStack {
+            id: None,
+            name: std::thread::current().name().map(String::from),
+            frames: capture(),
+        }]
+    }
+}
+
+/// Captures the stack of the calling thread, innermost frame first.
+///
+/// Inlined functions share the address of the frame they were inlined into and
+/// are reported as separate frames.
+pub(crate) fn capture() -> Vec<ThreadFrame> {
+    let mut frames = Vec::new();
+    backtrace::trace(|frame| {
+        let address = frame.ip() as usize;
+        let mut symbols = Vec::new();
+        backtrace::resolve_frame(frame, |symbol| symbols.push(frame_of(symbol, address)));
+        push_resolved(&mut frames, symbols, address);
+        true
+    });
+    frames
+}
+
+fn frame_of(symbol: &backtrace::Symbol, address: usize) -> ThreadFrame {
+    ThreadFrame {
+        function: symbol.name().map(|name| format!("{name:#}")),
+        file: symbol.filename().map(|file| file.display().to_string()),
+        line: symbol.lineno(),
+        address,
+    }
+}
+
+fn push_resolved(frames: &mut Vec<ThreadFrame>, symbols: Vec<ThreadFrame>, address: usize) {
+    if symbols.is_empty() {
+        frames.push(ThreadFrame {
+            function: None,
+            file: None,
+            line: None,
+            address,
+        });
+    } else {
+        frames.extend(symbols);
+    }
+}
+
+/// Renders each stack under a header naming the thread, a frame per entry with
+/// the source location below the function when known.
+pub(crate) fn render(stacks: &[ThreadStack]) -> String {
+    let mut out = String::new();
+    for stack in stacks {
+        let name = stack.name.as_deref().unwrap_or("<unnamed>");
+        match stack.id {
+            Some(id) => {
+                let _ = writeln!(out, "thread \"{name}\" #{id}");
+            }
+            None => {
+                let _ = writeln!(out, "thread \"{name}\"");
+            }
+        }
+        if stack.frames.is_empty() {
+            let _ = writeln!(out, "      <unavailable>");
+        }
+        for (i, frame) in stack.frames.iter().enumerate() {
+            let function = frame.function.as_deref().unwrap_or("<unknown>");
+            let _ = writeln!(out, "{i:4}: {:#018x} - {function}", frame.address);
+            if let Some(file) = &frame.file {
+                match frame.line {
+                    Some(line) => {
+                        let _ = writeln!(out, "{:28}at {file}:{line}", "");
+                    }
+                    None => {
+                        let _ = writeln!(out, "{:28}at {file}", "");
+                    }
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    use super::{ThreadFrame, ThreadStack, capture, frame_of, push_resolved};
+
+    const MAX_FRAMES: usize = 256;
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    /// No capture requested.
+    const IDLE: i64 = 0;
+    /// The signalled thread is recording its stack.
+    const WRITING: i64 = -1;
+    /// The signalled thread recorded its stack.
+    const DONE: i64 = -2;
+
+    /// Serializes the dumps, they share the buffers below.
+    static DUMP: Mutex<()> = Mutex::new(());
+    /// Thread id the capture is requested from, or one of the states above.
+    static TARGET: AtomicI64 = AtomicI64::new(IDLE);
+    static LEN: AtomicUsize = AtomicUsize::new(0);
+    static ADDRESSES: [AtomicUsize; MAX_FRAMES] = [const { AtomicUsize::new(0) }; MAX_FRAMES];
+
+    pub(super) fn capture_all() -> Vec<ThreadStack> {
+        let _dump = DUMP.lock().unwrap_or_else(|err| err.into_inner());
+        let current = gettid();
+        let mut stacks = vec![ThreadStack {
+            id: Some(current as u64),
+            name: name(current),
+            frames: capture(),
+        }];
+        let Ok(tasks) = std::fs::read_dir("/proc/self/task") else {
+            return stacks;
+        };
+        let mut tids: Vec<i64> = tasks
+            .filter_map(|task| task.ok()?.file_name().to_str()?.parse().ok())
+            .filter(|tid| *tid != current)
+            .collect();
+        tids.sort_unstable();
+
+        if !install_handler() {
+            return stacks;
+        }
+        for tid in tids {
+            stacks.push(ThreadStack {
+                id: Some(tid as u64),
+                name: name(tid),
+                frames: capture_remote(tid, signal()),
+            });
+        }
+        stacks
+    }
+
+    pub(super) fn signal() -> libc::c_int {
+        libc::SIGRTMIN() + 4
+    }
+
+    /// Installs the handler on the first dump and keeps it for good: restoring
+    /// the default action would let a signal still pending on a thread too slow
+    /// to answer terminate the process. Returns whether it is installed.
+    fn install_handler() -> bool {
+        static INSTALLED: OnceLock<bool> = OnceLock::new();
+        // SAFETY: the handler only touches atomics and unwinds without
+        // allocating, ignoring the signals no capture is requested for.
+        *INSTALLED.get_or_init(|| unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal(), &action, std::ptr::null_mut()) == 0
+        })
+    }
+
+    /// Signals the thread and resolves the addresses it recorded, empty when it
+    /// does not answer in time.
+    fn capture_remote(tid: i64, signal: libc::c_int) -> Vec<ThreadFrame> {
+        TARGET.store(tid, Ordering::SeqCst);
+        // SAFETY: tgkill only delivers the signal to the thread of this process.
+        let sent = unsafe { libc::syscall(libc::SYS_tgkill, libc::getpid(), tid, signal) } == 0;
+        if !sent {
+            TARGET.store(IDLE, Ordering::SeqCst);
+            return Vec::new();
+        }
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            match TARGET.load(Ordering::Acquire) {
+                DONE => break,
+                WRITING => std::thread::yield_now(),
+                _ if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(1)),
+                _ => {
+                    // A handler running late finds the request withdrawn.
+                    if TARGET
+                        .compare_exchange(tid, IDLE, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok()
+                    {
+                        return Vec::new();
+                    }
+                }
+            }
+        }
+        let len = LEN.load(Ordering::Acquire);
+        let addresses: Vec<_> = ADDRESSES[..len]
+            .iter()
+            .map(|address| address.load(Ordering::Relaxed))
+            .collect();
+        TARGET.store(IDLE, Ordering::SeqCst);
+
+        let mut frames = Vec::with_capacity(len);
+        for address in addresses {
+            let mut symbols = Vec::new();
+            // Return addresses point past the call, step back into it.
+            let lookup = address.saturating_sub(1) as *mut c_void;
+            backtrace::resolve(lookup, |symbol| symbols.push(frame_of(symbol, address)));
+            push_resolved(&mut frames, symbols, address);
+        }
+        // Leave out the unwinding done by the handler itself.
+        if let Some(handler) = frames.iter().position(|frame| {
+            frame
+                .function
+                .as_deref()
+                .is_some_and(|function| function.ends_with("thread_dump::linux::handler"))
+        }) {
+            frames.drain(..=handler);
+        }
+        frames
+    }
+
+    extern "C" fn handler(_: libc::c_int) {
+        if TARGET
+            .compare_exchange(gettid(), WRITING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return;
+        }
+        let mut len = 0;
+        // SAFETY: dumps are serialized, so no other unwinding goes through the
+        // buffers concurrently.
+        unsafe {
+            backtrace::trace_unsynchronized(|frame| {
+                ADDRESSES[len].store(frame.ip() as usize, Ordering::Relaxed);
+                len += 1;
+                len < MAX_FRAMES
+            });
+        }
+        LEN.store(len, Ordering::Release);
+        TARGET.store(DONE, Ordering::Release);
+    }
+
+    fn gettid() -> i64 {
+        // SAFETY: gettid has no preconditions.
+        unsafe { libc::syscall(libc::SYS_gettid) }
+    }
+
+    fn name(tid: i64) -> Option<String> {
+        let comm = std::fs::read_to_string(format!("/proc/self/task/{tid}/comm")).ok()?;
+        Some(comm.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    #[test]
+    fn test_capture_resolves_crate_frame() {
+        let frames = capture();
+        assert!(
+            frames
+                .iter()
+                .filter_map(|frame| frame.function.as_deref())
+                .any(|function| function.starts_with("rust_actuator::thread_dump::")),
+            "{frames:#?}"
+        );
+    }
+
+    #[test]
+    fn test_capture_all_named_threads() {
+        let started = Arc::new(Barrier::new(3));
+        let release = Arc::new(Barrier::new(3));
+        let workers: Vec<_> = ["dump-worker-a", "dump-worker-b"]
+            .into_iter()
+            .map(|name| {
+                let (started, release) = (started.clone(), release.clone());
+                std::thread::Builder::new()
+                    .name(name.to_string())
+                    .spawn(move || {
+                        started.wait();
+                        release.wait();
+                    })
+                    .unwrap()
+            })
+            .collect();
+        started.wait();
+        let stacks = capture_all();
+        release.wait();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert!(!stacks[0].frames.is_empty());
+        for name in ["dump-worker-a", "dump-worker-b"] {
+            let stack = stacks
+                .iter()
+                .find(|stack| stack.name.as_deref() == Some(name));
+            if cfg!(target_os = "linux") {
+                let stack = stack.unwrap_or_else(|| panic!("missing {name}"));
+                assert!(stack.id.is_some());
+                assert!(!stack.frames.iter().any(|frame| frame.function.as_deref()
+                    == Some("rust_actuator::thread_dump::linux::handler")));
+                assert!(
+                    stack
+                        .frames
+                        .iter()
+                        .filter_map(|frame| frame.function.as_deref())
+                        // `std::sync::barrier::Barrier::wait` on older toolchains.
+                        .any(
+                            |function| function.contains("Barrier") && function.ends_with("::wait")
+                        ),
+                    "{stack:#?}"
+                );
+            }
+        }
+        if cfg!(target_os = "linux") {
+            let text = render(&stacks);
+            assert!(text.contains("thread \"dump-worker-a\" #"));
+            assert!(text.contains("thread \"dump-worker-b\" #"));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_capture_all_keeps_handler() {
+        capture_all();
+        capture_all();
+        // A signal arriving after the dump is ignored instead of terminating
+        // the process.
+        // SAFETY: only signals the calling thread.
+        unsafe {
+            let sent = libc::syscall(
+                libc::SYS_tgkill,
+                libc::getpid(),
+                libc::syscall(libc::SYS_gettid),
+                linux::signal(),
+            );
+            assert_eq!(sent, 0);
+            let mut action: libc::sigaction = std::mem::zeroed();
+            assert_eq!(
+                libc::sigaction(linux::signal(), std::ptr::null(), &mut action),
+                0
+            );
+            assert_ne!(action.sa_sigaction, libc::SIG_DFL);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let stacks = [ThreadStack {
+            id: Some(7),
+            name: Some("main".to_string()),
+            frames: vec![
+                ThreadFrame {
+                    function: Some("rust_actuator::main".to_string()),
+                    file: Some("src/main.rs".to_string()),
+                    line: Some(12),
+                    address: 0x1f,
+                },
+                ThreadFrame {
+                    function: None,
+                    file: None,
+                    line: None,
+                    address: 0x2a,
+                },
+            ],
+        }];
+        assert_eq!(
+            render(&stacks),
+            "thread \"main\" #7\n   \
+             0: 0x000000000000001f - rust_actuator::main\n\
+             \x20                           at src/main.rs:12\n\
+             \x20  1: 0x000000000000002a - <unknown>\n\n"
+        );
+    }
+
+    #[test]
+    fn test_render_unavailable() {
+        let stacks = [ThreadStack {
+            id: None,
+            name: None,
+            frames: Vec::new(),
+        }];
+        assert_eq!(
+            render(&stacks),
+            "thread \"<unnamed>\"\n      <unavailable>\n\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let stack = ThreadStack {
+            id: Some(7),
+            name: Some("main".to_string()),
+            frames: vec![ThreadFrame {
+                function: Some("rust_actuator::main".to_string()),
+                file: None,
+                line: None,
+                address: 42,
+            }],
+        };
+        let json = serde_json::to_value(stack).unwrap();
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["name"], "main");
+        assert_eq!(json["frames"][0]["function"], "rust_actuator::main");
+        assert_eq!(json["frames"][0]["file"], serde_json::Value::Null);
+        assert_eq!(json["frames"][0]["address"], 42);
+    }
+}